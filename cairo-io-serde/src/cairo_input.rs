@@ -1,33 +1,277 @@
 use cainome_cairo_serde::ByteArray;
 use cairo_vm::Felt252;
+use num_bigint::{BigInt, BigUint};
+use num_traits::ToPrimitive;
 use serde_json::Value;
 use std::str::FromStr;
 
 use crate::{
-    schema::{Schema, SchemaType},
+    error::{field_path, index_path, IoSerdeError, ROOT_PATH},
+    schema::{CairoInput, Schema, SchemaType},
     utils::is_valid_number,
     FuncArg, FuncArgs,
 };
 
-pub fn process_json_args(json_str: &str, schema: &Schema) -> Result<FuncArgs, String> {
-    let json: Value =
-        serde_json::from_str(json_str).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+fn parse_json(json_str: &str) -> Result<Value, IoSerdeError> {
+    serde_json::from_str(json_str).map_err(|e| IoSerdeError::InvalidJson {
+        path: ROOT_PATH.to_string(),
+        message: format!("Failed to parse JSON: {}", e),
+    })
+}
+
+pub fn process_json_args(json_str: &str, schema: &Schema) -> Result<FuncArgs, IoSerdeError> {
+    let json = parse_json(json_str)?;
 
     if json.as_object().map_or(false, |obj| obj.is_empty()) {
         // Return default (empty) FuncArgs if JSON is empty
         return Ok(FuncArgs::default());
     }
 
-    let parsed = parse_schema(&json, &schema.cairo_input, schema)?;
+    match &schema.cairo_input {
+        CairoInput::Schema(schema_name) => {
+            let parsed = parse_schema(&json, schema_name, schema, ROOT_PATH)?;
+            Ok(FuncArgs(vec![FuncArg::Array(parsed)]))
+        }
+        CairoInput::Params(params) => {
+            let mut args = Vec::new();
+            for param in params {
+                let param_value =
+                    json.get(&param.name)
+                        .ok_or_else(|| IoSerdeError::MissingField {
+                            path: ROOT_PATH.to_string(),
+                            field: param.name.clone(),
+                        })?;
+                let path = field_path(ROOT_PATH, &param.name);
+                parse_param(param_value, &param.ty, schema, &path, &mut args)?;
+            }
+            Ok(FuncArgs(args))
+        }
+    }
+}
 
-    Ok(FuncArgs(vec![FuncArg::Array(parsed)]))
+/// Serializes the arguments with Cairo Serde, one after the other.
+/// This is the `Array<felt252>` input of a proof-mode wrapper, which deserializes each argument
+/// of the function it wraps from it.
+pub fn serialize_json_args(json_str: &str, schema: &Schema) -> Result<Vec<Felt252>, IoSerdeError> {
+    let json = parse_json(json_str)?;
+
+    match &schema.cairo_input {
+        CairoInput::Schema(schema_name) if schema_name.is_empty() => Ok(Vec::new()),
+        CairoInput::Schema(schema_name) => {
+            // The function takes the serialized input as a single `Array<felt252>`
+            let parsed = parse_schema(&json, schema_name, schema, ROOT_PATH)?;
+            let mut serialized = vec![Felt252::from(parsed.len())];
+            serialized.extend(parsed);
+            Ok(serialized)
+        }
+        CairoInput::Params(params) => {
+            let mut serialized = Vec::new();
+            for param in params {
+                let param_value =
+                    json.get(&param.name)
+                        .ok_or_else(|| IoSerdeError::MissingField {
+                            path: ROOT_PATH.to_string(),
+                            field: param.name.clone(),
+                        })?;
+                let path = field_path(ROOT_PATH, &param.name);
+                serialized.extend(parse_value(param_value, &param.ty, schema, &path)?);
+            }
+            Ok(serialized)
+        }
+    }
 }
 
-fn parse_schema(value: &Value, schema_name: &str, schema: &Schema) -> Result<Vec<Felt252>, String> {
+// Lays out a function param the way the VM expects it in memory: arrays and spans are loaded into
+// their own segment, and enums hold a variant selector followed by a zero padding and the payload.
+fn parse_param(
+    value: &Value,
+    ty: &SchemaType,
+    schema: &Schema,
+    path: &str,
+    args: &mut Vec<FuncArg>,
+) -> Result<(), IoSerdeError> {
+    match ty {
+        SchemaType::Primitive { name } if name == "ByteArray" => {
+            let string = value.as_str().ok_or_else(|| {
+                IoSerdeError::type_mismatch(path, "Expected string for ByteArray")
+            })?;
+            // Serialized as the data length, the data words, the pending word and its length
+            let serialized = parse_byte_array(string, path)?;
+            if let Some((data, pending)) = serialized[1..].split_last_chunk::<2>() {
+                args.push(FuncArg::Array(data.to_vec()));
+                args.extend(pending.iter().map(|felt| FuncArg::Single(*felt)));
+            }
+        }
+        SchemaType::Primitive { .. } => {
+            let parsed = parse_value(value, ty, schema, path)?;
+            args.extend(parsed.into_iter().map(FuncArg::Single));
+        }
+        SchemaType::Array { item_type } | SchemaType::Span { item_type } => {
+            let array = value
+                .as_array()
+                .ok_or_else(|| IoSerdeError::type_mismatch(path, "Expected array"))?;
+            let mut items = Vec::new();
+            for (i, item) in array.iter().enumerate() {
+                items.extend(parse_array_item(
+                    item,
+                    item_type,
+                    schema,
+                    &index_path(path, i),
+                )?);
+            }
+            args.push(FuncArg::Array(items));
+        }
+        SchemaType::Tensor { item_type } => {
+            // Serialized as the shape span followed by the data span
+            let serialized = parse_tensor(value, item_type, schema, path)?;
+            let invalid_shape = || IoSerdeError::type_mismatch(path, "Invalid Tensor shape length");
+            let shape_len = serialized
+                .first()
+                .and_then(Felt252::to_usize)
+                .ok_or_else(invalid_shape)?;
+            let (shape, data) = (
+                serialized.get(1..=shape_len).ok_or_else(invalid_shape)?,
+                serialized.get(shape_len + 2..).ok_or_else(invalid_shape)?,
+            );
+            args.push(FuncArg::Array(shape.to_vec()));
+            args.push(FuncArg::Array(data.to_vec()));
+        }
+        SchemaType::Struct { name } => {
+            let schema_def = schema
+                .schemas
+                .get(name)
+                .ok_or_else(|| IoSerdeError::unknown_schema(path, format!("Schema {}", name)))?;
+            for field in &schema_def.fields {
+                let field_value =
+                    value
+                        .get(&field.name)
+                        .ok_or_else(|| IoSerdeError::MissingField {
+                            path: path.to_string(),
+                            field: field.name.clone(),
+                        })?;
+                parse_param(
+                    field_value,
+                    &field.ty,
+                    schema,
+                    &field_path(path, &field.name),
+                    args,
+                )?;
+            }
+        }
+        SchemaType::Tuple { items } => {
+            let array = parse_tuple(value, items.len(), path)?;
+            for (i, (item, item_type)) in array.iter().zip(items).enumerate() {
+                parse_param(item, item_type, schema, &index_path(path, i), args)?;
+            }
+        }
+        SchemaType::Option { item_type } => {
+            // Some is variant 0 and None is variant 1
+            let payload_size = schema
+                .type_size(item_type)
+                .map_err(|e| IoSerdeError::type_mismatch(path, e))?;
+            let mut payload = Vec::new();
+            let variant_idx = if value.is_null() {
+                1
+            } else {
+                parse_param(value, item_type, schema, path, &mut payload)?;
+                0
+            };
+            push_enum(variant_idx, 2, payload_size, payload, args);
+        }
+        SchemaType::Enum { name } => {
+            let (variant_idx, variant_ty, payload_value) = find_variant(value, name, schema, path)?;
+            let enum_def = &schema.enums[name];
+            let mut payload_size = 0;
+            for variant in &enum_def.variants {
+                if let Some(ty) = &variant.ty {
+                    let variant_size = schema
+                        .type_size(ty)
+                        .map_err(|e| IoSerdeError::type_mismatch(path, e))?;
+                    payload_size = payload_size.max(variant_size);
+                }
+            }
+            let mut payload = Vec::new();
+            if let (Some(ty), Some(payload_value)) = (variant_ty, payload_value) {
+                let variant_name = &enum_def.variants[variant_idx].name;
+                parse_param(
+                    payload_value,
+                    ty,
+                    schema,
+                    &field_path(path, variant_name),
+                    &mut payload,
+                )?;
+            }
+            push_enum(
+                variant_idx,
+                enum_def.variants.len(),
+                payload_size,
+                payload,
+                args,
+            );
+        }
+    }
+    Ok(())
+}
+
+// Array items are stored next to each other in the array segment, so they can't contain arrays.
+fn parse_array_item(
+    value: &Value,
+    ty: &SchemaType,
+    schema: &Schema,
+    path: &str,
+) -> Result<Vec<Felt252>, IoSerdeError> {
+    let mut item_args = Vec::new();
+    parse_param(value, ty, schema, path, &mut item_args)?;
+    item_args
+        .into_iter()
+        .map(|arg| match arg {
+            FuncArg::Single(felt) => Ok(felt),
+            FuncArg::Array(_) => Err(IoSerdeError::type_mismatch(
+                path,
+                "Nested arrays are not supported in function params",
+            )),
+        })
+        .collect()
+}
+
+// Mirrors `enum_init` in the Sierra to CASM compiler: the variant selector is the variant index
+// for enums with up to two variants, and the relative jump into the `enum_match` jump table otherwise.
+fn push_enum(
+    variant_idx: usize,
+    variants_count: usize,
+    payload_size: i16,
+    payload: Vec<FuncArg>,
+    args: &mut Vec<FuncArg>,
+) {
+    let selector = if variants_count <= 2 {
+        variant_idx
+    } else {
+        (variants_count - variant_idx) * 2 - 1
+    };
+    args.push(FuncArg::Single(Felt252::from(selector)));
+    let variant_size: i16 = payload
+        .iter()
+        .map(|arg| match arg {
+            FuncArg::Single(_) => 1,
+            FuncArg::Array(_) => 2,
+        })
+        .sum();
+    for _ in variant_size..payload_size {
+        args.push(FuncArg::Single(Felt252::ZERO));
+    }
+    args.extend(payload);
+}
+
+fn parse_schema(
+    value: &Value,
+    schema_name: &str,
+    schema: &Schema,
+    path: &str,
+) -> Result<Vec<Felt252>, IoSerdeError> {
     let schema_def = schema
         .schemas
         .get(schema_name)
-        .ok_or_else(|| format!("Schema {} not found in schema", schema_name))?;
+        .ok_or_else(|| IoSerdeError::unknown_schema(path, format!("Schema {}", schema_name)))?;
 
     let mut args = Vec::new();
 
@@ -36,84 +280,398 @@ fn parse_schema(value: &Value, schema_name: &str, schema: &Schema) -> Result<Vec
     for field in &schema_def.fields {
         let field_value = value
             .get(&field.name)
-            .ok_or_else(|| format!("Missing field: {} from schema {} in {}", field.name, schema_name, value))?;
-
-        let parsed = parse_value(field_value, &field.ty, schema)?;
+            .ok_or_else(|| IoSerdeError::MissingField {
+                path: path.to_string(),
+                field: field.name.clone(),
+            })?;
+
+        let parsed = parse_value(
+            field_value,
+            &field.ty,
+            schema,
+            &field_path(path, &field.name),
+        )?;
         args.extend(parsed);
     }
 
     Ok(args)
 }
 
-fn parse_value(value: &Value, ty: &SchemaType, schema: &Schema) -> Result<Vec<Felt252>, String> {
+fn parse_value(
+    value: &Value,
+    ty: &SchemaType,
+    schema: &Schema,
+    path: &str,
+) -> Result<Vec<Felt252>, IoSerdeError> {
+    let out_of_range =
+        |name: &str| IoSerdeError::type_mismatch(path, format!("Value out of range for {}", name));
+
     match ty {
         SchemaType::Primitive { name } => match name.as_str() {
             "u64" | "u32" | "u16" | "u8" => {
-                let num = value
-                    .as_u64()
-                    .ok_or_else(|| format!("Expected unsigned integer for {}", name))?;
+                let num = value.as_u64().ok_or_else(|| {
+                    IoSerdeError::type_mismatch(
+                        path,
+                        format!("Expected unsigned integer for {}", name),
+                    )
+                })?;
                 Ok(vec![Felt252::from(num)])
             }
             "i64" | "i32" | "i16" | "i8" => {
-                let num = value
-                    .as_i64()
-                    .ok_or_else(|| format!("Expected signed integer for {}", name))?;
+                let num = value.as_i64().ok_or_else(|| {
+                    IoSerdeError::type_mismatch(
+                        path,
+                        format!("Expected signed integer for {}", name),
+                    )
+                })?;
+                Ok(vec![Felt252::from(num)])
+            }
+            "u128" => {
+                let num = parse_wide_integer(value, name, path)?
+                    .to_u128()
+                    .ok_or_else(|| out_of_range(name))?;
+                Ok(vec![Felt252::from(num)])
+            }
+            "i128" => {
+                let num = parse_wide_integer(value, name, path)?
+                    .to_i128()
+                    .ok_or_else(|| out_of_range(name))?;
                 Ok(vec![Felt252::from(num)])
             }
+            "u256" => {
+                let num = parse_wide_integer(value, name, path)?
+                    .to_biguint()
+                    .filter(|num| num.bits() <= 256)
+                    .ok_or_else(|| out_of_range(name))?;
+                // u256 is serialized as its low and high 128-bit limbs
+                let low = (&num & BigUint::from(u128::MAX))
+                    .to_u128()
+                    .ok_or_else(|| out_of_range(name))?;
+                let high = (num >> 128u32)
+                    .to_u128()
+                    .ok_or_else(|| out_of_range(name))?;
+                Ok(vec![Felt252::from(low), Felt252::from(high)])
+            }
             "F64" => {
-                let num = value
-                    .as_f64()
-                    .ok_or_else(|| format!("Expected float for {}", name))?;
+                let num = value.as_f64().ok_or_else(|| {
+                    IoSerdeError::type_mismatch(path, format!("Expected float for {}", name))
+                })?;
                 Ok(vec![Felt252::from((num * 2.0_f64.powi(32)) as i64)])
             }
             "felt252" => {
                 let string = value
                     .as_str()
-                    .ok_or_else(|| "Expected a string".to_string())?;
+                    .ok_or_else(|| IoSerdeError::type_mismatch(path, "Expected a string"))?;
 
                 // Check if the string is a valid number
-                if is_valid_number(string) || string.starts_with("0x") {
-                    Ok(vec![Felt252::from_str(string).map_err(|e| e.to_string())?])
+                let felt = if is_valid_number(string) || string.starts_with("0x") {
+                    Felt252::from_str(string)
                 } else {
-                    Ok(vec![Felt252::from_str(
-                        &("0x".to_string() + &hex::encode(string)),
-                    )
-                    .map_err(|e| e.to_string())?])
-                }
+                    Felt252::from_str(&("0x".to_string() + &hex::encode(string)))
+                };
+                Ok(vec![felt.map_err(|e| {
+                    IoSerdeError::type_mismatch(path, e.to_string())
+                })?])
             }
             "ByteArray" => {
-                let string = value
-                    .as_str()
-                    .ok_or_else(|| "Expected string for ByteArray".to_string())?;
-                parse_byte_array(string)
+                let string = value.as_str().ok_or_else(|| {
+                    IoSerdeError::type_mismatch(path, "Expected string for ByteArray")
+                })?;
+                parse_byte_array(string, path)
             }
             "bool" => {
                 let bool_value = value
                     .as_bool()
-                    .ok_or_else(|| "Expected boolean value".to_string())?;
+                    .ok_or_else(|| IoSerdeError::type_mismatch(path, "Expected boolean value"))?;
                 Ok(vec![Felt252::from(bool_value as u64)])
             }
-            _ => Err(format!("Unknown primitive type: {}", name)),
+            _ => Err(IoSerdeError::unknown_schema(
+                path,
+                format!("Primitive type {}", name),
+            )),
         },
         SchemaType::Array { item_type } | SchemaType::Span { item_type } => {
             let array = value
                 .as_array()
-                .ok_or_else(|| "Expected array".to_string())?;
+                .ok_or_else(|| IoSerdeError::type_mismatch(path, "Expected array"))?;
             let mut result = Vec::new();
             result.push(Felt252::from(array.len()));
-            for item in array {
-                let parsed = parse_value(item, item_type, schema)?;
+            for (i, item) in array.iter().enumerate() {
+                let parsed = parse_value(item, item_type, schema, &index_path(path, i))?;
+                result.extend(parsed);
+            }
+            Ok(result)
+        }
+        SchemaType::Struct { name } => parse_schema(value, name, schema, path),
+        SchemaType::Tensor { item_type } => parse_tensor(value, item_type, schema, path),
+        SchemaType::Enum { name } => parse_enum(value, name, schema, path),
+        SchemaType::Option { item_type } => {
+            // Option<T> is serialized as an enum with variants Some(T) = 0 and None = 1
+            if value.is_null() {
+                Ok(vec![Felt252::from(1)])
+            } else {
+                let mut result = vec![Felt252::from(0)];
+                result.extend(parse_value(value, item_type, schema, path)?);
+                Ok(result)
+            }
+        }
+        SchemaType::Tuple { items } => {
+            let array = parse_tuple(value, items.len(), path)?;
+            let mut result = Vec::new();
+            for (i, (item, item_type)) in array.iter().zip(items).enumerate() {
+                let parsed = parse_value(item, item_type, schema, &index_path(path, i))?;
                 result.extend(parsed);
             }
             Ok(result)
         }
-        SchemaType::Struct { name } => parse_schema(value, name, schema).map(|func_args| func_args),
     }
 }
 
-fn parse_byte_array(string: &str) -> Result<Vec<Felt252>, String> {
-    let byte_array =
-        ByteArray::from_string(string).map_err(|e| format!("Error parsing ByteArray: {}", e))?;
+fn parse_tuple<'a>(
+    value: &'a Value,
+    len: usize,
+    path: &str,
+) -> Result<&'a Vec<Value>, IoSerdeError> {
+    let array = value
+        .as_array()
+        .ok_or_else(|| IoSerdeError::type_mismatch(path, "Expected array for Tuple"))?;
+    if array.len() != len {
+        return Err(IoSerdeError::type_mismatch(
+            path,
+            format!("Expected tuple of {} elements, got {}", len, array.len()),
+        ));
+    }
+    Ok(array)
+}
+
+// An enum is given either as the variant name (for variants without payload)
+// or as a single-key object mapping the variant name to its payload.
+fn parse_enum(
+    value: &Value,
+    enum_name: &str,
+    schema: &Schema,
+    path: &str,
+) -> Result<Vec<Felt252>, IoSerdeError> {
+    let (variant_idx, variant_ty, payload) = find_variant(value, enum_name, schema, path)?;
+
+    let mut result = vec![Felt252::from(variant_idx)];
+    if let (Some(ty), Some(payload)) = (variant_ty, payload) {
+        let variant_name = &schema.enums[enum_name].variants[variant_idx].name;
+        result.extend(parse_value(
+            payload,
+            ty,
+            schema,
+            &field_path(path, variant_name),
+        )?);
+    }
+    Ok(result)
+}
+
+// Returns the index, payload type and payload value of the variant given in `value`
+fn find_variant<'a>(
+    value: &'a Value,
+    enum_name: &str,
+    schema: &'a Schema,
+    path: &str,
+) -> Result<(usize, Option<&'a SchemaType>, Option<&'a Value>), IoSerdeError> {
+    let enum_def = schema
+        .enums
+        .get(enum_name)
+        .ok_or_else(|| IoSerdeError::unknown_schema(path, format!("Enum {}", enum_name)))?;
+
+    let (variant_name, payload) = match value {
+        Value::String(name) => (name.as_str(), None),
+        Value::Object(obj) if obj.len() == 1 => match obj.iter().next() {
+            Some((name, payload)) => (name.as_str(), Some(payload)),
+            None => unreachable!(),
+        },
+        _ => {
+            return Err(IoSerdeError::type_mismatch(
+                path,
+                format!(
+                    "Expected variant name or single-key object for enum {}",
+                    enum_name
+                ),
+            ))
+        }
+    };
+
+    let (variant_idx, variant) = enum_def
+        .variants
+        .iter()
+        .enumerate()
+        .find(|(_, variant)| variant.name == variant_name)
+        .ok_or_else(|| {
+            IoSerdeError::type_mismatch(
+                path,
+                format!("Unknown variant: {} for enum {}", variant_name, enum_name),
+            )
+        })?;
+
+    match (&variant.ty, payload) {
+        (Some(_), Some(_)) => {}
+        (Some(_), None) => {
+            return Err(IoSerdeError::type_mismatch(
+                path,
+                format!(
+                    "Missing payload for variant {} of enum {}",
+                    variant_name, enum_name
+                ),
+            ))
+        }
+        (None, Some(payload)) if !payload.is_null() => {
+            return Err(IoSerdeError::type_mismatch(
+                path,
+                format!(
+                    "Unexpected payload for variant {} of enum {}",
+                    variant_name, enum_name
+                ),
+            ))
+        }
+        (None, _) => {}
+    }
+    Ok((variant_idx, variant.ty.as_ref(), payload))
+}
+
+// A tensor is serialized as its shape span followed by its data span.
+// It can be given either as a `{shape, data}` object or as a nested array, whose shape is inferred.
+fn parse_tensor(
+    value: &Value,
+    item_type: &SchemaType,
+    schema: &Schema,
+    path: &str,
+) -> Result<Vec<Felt252>, IoSerdeError> {
+    let (shape, data) = match value {
+        Value::Object(obj) => {
+            let shape = obj
+                .get("shape")
+                .and_then(Value::as_array)
+                .ok_or_else(|| {
+                    IoSerdeError::type_mismatch(path, "Expected shape array for Tensor")
+                })?
+                .iter()
+                .map(|dim| {
+                    dim.as_u64().ok_or_else(|| {
+                        IoSerdeError::type_mismatch(
+                            &field_path(path, "shape"),
+                            "Expected unsigned integer in Tensor shape",
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let data = obj
+                .get("data")
+                .and_then(Value::as_array)
+                .ok_or_else(|| IoSerdeError::type_mismatch(path, "Expected data array for Tensor"))?
+                .iter()
+                .collect::<Vec<_>>();
+            (shape, data)
+        }
+        Value::Array(_) => {
+            let mut shape = Vec::new();
+            let mut data = Vec::new();
+            flatten_tensor(value, 0, &mut shape, &mut data)
+                .map_err(|e| IoSerdeError::type_mismatch(path, e))?;
+            (shape, data)
+        }
+        _ => {
+            return Err(IoSerdeError::type_mismatch(
+                path,
+                "Expected nested array or {shape, data} object for Tensor",
+            ))
+        }
+    };
+
+    let expected_len: u64 = shape.iter().product();
+    if expected_len != data.len() as u64 {
+        return Err(IoSerdeError::type_mismatch(
+            path,
+            format!(
+                "Tensor shape {:?} expects {} elements, got {}",
+                shape,
+                expected_len,
+                data.len()
+            ),
+        ));
+    }
+
+    let data_path = field_path(path, "data");
+    let mut result = Vec::new();
+    result.push(Felt252::from(shape.len()));
+    result.extend(shape.iter().map(|dim| Felt252::from(*dim)));
+    result.push(Felt252::from(data.len()));
+    for (i, item) in data.into_iter().enumerate() {
+        let parsed = parse_value(item, item_type, schema, &index_path(&data_path, i))?;
+        result.extend(parsed);
+    }
+    Ok(result)
+}
+
+// Walks a nested array, recording the length of each dimension the first time it is reached
+// and collecting the leaves in row-major order.
+fn flatten_tensor<'a>(
+    value: &'a Value,
+    depth: usize,
+    shape: &mut Vec<u64>,
+    data: &mut Vec<&'a Value>,
+) -> Result<(), String> {
+    match value {
+        Value::Array(items) => {
+            let len = items.len() as u64;
+            match shape.get(depth) {
+                Some(dim) if *dim == len => {}
+                None if depth == shape.len() && data.is_empty() => shape.push(len),
+                _ => return Err("Ragged nested array for Tensor".to_string()),
+            }
+            for item in items {
+                flatten_tensor(item, depth + 1, shape, data)?;
+            }
+            Ok(())
+        }
+        _ if depth == shape.len() => {
+            data.push(value);
+            Ok(())
+        }
+        _ => Err("Ragged nested array for Tensor".to_string()),
+    }
+}
+
+// Wide integers may not fit in a JSON number, so they are also accepted as decimal or hex strings.
+fn parse_wide_integer(value: &Value, name: &str, path: &str) -> Result<BigInt, IoSerdeError> {
+    match value {
+        Value::Number(num) => num
+            .as_i64()
+            .map(BigInt::from)
+            .or_else(|| num.as_u64().map(BigInt::from))
+            .ok_or_else(|| {
+                IoSerdeError::type_mismatch(path, format!("Expected integer for {}", name))
+            }),
+        Value::String(string) => {
+            let parsed = if let Some(hex) = string.strip_prefix("0x") {
+                BigInt::parse_bytes(hex.as_bytes(), 16)
+            } else if is_valid_number(string) {
+                BigInt::parse_bytes(string.as_bytes(), 10)
+            } else {
+                None
+            };
+            parsed.ok_or_else(|| {
+                IoSerdeError::type_mismatch(
+                    path,
+                    format!("Expected decimal or hex string for {}", name),
+                )
+            })
+        }
+        _ => Err(IoSerdeError::type_mismatch(
+            path,
+            format!("Expected integer or string for {}", name),
+        )),
+    }
+}
+
+fn parse_byte_array(string: &str, path: &str) -> Result<Vec<Felt252>, IoSerdeError> {
+    let byte_array = ByteArray::from_string(string).map_err(|e| {
+        IoSerdeError::type_mismatch(path, format!("Error parsing ByteArray: {}", e))
+    })?;
 
     let mut result = Vec::new();
     let mut data = byte_array.data.iter().map(|b| b.felt()).collect::<Vec<_>>();
@@ -447,6 +1005,339 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tensor() {
+        let input_schema = r#"
+        schemas:
+            Input:
+                fields:
+                    - request:
+                        type: Tensor
+                        item_type:
+                            type: Primitive
+                            name: i32
+        cairo_input: Input
+        cairo_output: null
+        "#;
+
+        let schema_file = create_temp_file_with_content(input_schema);
+        let input_schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let expected = FuncArg::Array(vec![
+            Felt252::from(2), // Len of shape
+            Felt252::from(2), // shape[0]
+            Felt252::from(3), // shape[1]
+            Felt252::from(6), // Len of data
+            Felt252::from(1),
+            Felt252::from(2),
+            Felt252::from(3),
+            Felt252::from(4),
+            Felt252::from(5),
+            Felt252::from(-6),
+        ]);
+
+        // Case 1: nested array
+        let json = json!({"request": [[1, 2, 3], [4, 5, -6]]});
+        let result = process_json_args(&json.to_string(), &input_schema).unwrap();
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0], expected);
+
+        // Case 2: shape and data object
+        let json = json!({"request": {"shape": [2, 3], "data": [1, 2, 3, 4, 5, -6]}});
+        let result = process_json_args(&json.to_string(), &input_schema).unwrap();
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(result.0[0], expected);
+    }
+
+    #[test]
+    fn test_invalid_tensor() {
+        let input_schema = r#"
+        schemas:
+            Input:
+                fields:
+                    - request:
+                        type: Tensor
+                        item_type:
+                            type: Primitive
+                            name: u32
+        cairo_input: Input
+        cairo_output: null
+        "#;
+
+        let schema_file = create_temp_file_with_content(input_schema);
+        let input_schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let json = json!({"request": [[1, 2], [3]]});
+        let result = process_json_args(&json.to_string(), &input_schema);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Ragged nested array"));
+
+        let json = json!({"request": {"shape": [2, 2], "data": [1, 2, 3]}});
+        let result = process_json_args(&json.to_string(), &input_schema);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("expects 4 elements, got 3"));
+    }
+
+    #[test]
+    fn test_enum_option_tuple() {
+        let input_schema = r#"
+        schemas:
+            Input:
+                fields:
+                    - action:
+                        type: Enum
+                        name: Action
+                    - idle:
+                        type: Enum
+                        name: Action
+                    - some:
+                        type: Option
+                        item_type:
+                            type: Primitive
+                            name: u32
+                    - none:
+                        type: Option
+                        item_type:
+                            type: Primitive
+                            name: u32
+                    - pair:
+                        type: Tuple
+                        items:
+                            - type: Primitive
+                              name: u32
+                            - type: Primitive
+                              name: bool
+        enums:
+            Action:
+                variants:
+                    - Idle
+                    - Buy:
+                        type: Primitive
+                        name: u64
+        cairo_input: Input
+        cairo_output: null
+        "#;
+
+        let schema_file = create_temp_file_with_content(input_schema);
+        let input_schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let json = json!({
+            "action": {"Buy": 10},
+            "idle": "Idle",
+            "some": 7,
+            "none": null,
+            "pair": [3, true]
+        });
+
+        let result = process_json_args(&json.to_string(), &input_schema).unwrap();
+
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(
+            result.0[0],
+            FuncArg::Array(vec![
+                Felt252::from(1),  // Action::Buy
+                Felt252::from(10), // Buy payload
+                Felt252::from(0),  // Action::Idle
+                Felt252::from(0),  // Option::Some
+                Felt252::from(7),  // Some payload
+                Felt252::from(1),  // Option::None
+                Felt252::from(3),  // Tuple first item
+                Felt252::from(1),  // Tuple second item
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unknown_enum_variant() {
+        let input_schema = r#"
+        schemas:
+            Input:
+                fields:
+                    - action:
+                        type: Enum
+                        name: Action
+        enums:
+            Action:
+                variants:
+                    - Idle
+        cairo_input: Input
+        cairo_output: null
+        "#;
+
+        let schema_file = create_temp_file_with_content(input_schema);
+        let input_schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+        let json = json!({"action": "Sell"});
+
+        let result = process_json_args(&json.to_string(), &input_schema);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown variant: Sell for enum Action"));
+    }
+
+    #[test]
+    fn test_wide_integers() {
+        let input_schema = r#"
+        schemas:
+            Input:
+                fields:
+                    - unsigned:
+                        type: Primitive
+                        name: u128
+                    - signed:
+                        type: Primitive
+                        name: i128
+                    - wide:
+                        type: Primitive
+                        name: u256
+                    - small:
+                        type: Primitive
+                        name: u256
+        cairo_input: Input
+        cairo_output: null
+        "#;
+
+        let schema_file = create_temp_file_with_content(input_schema);
+        let input_schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let json = json!({
+            "unsigned": "340282366920938463463374607431768211455",
+            "signed": "-170141183460469231731687303715884105728",
+            "wide": "0x10000000000000000000000000000000002",
+            "small": 42
+        });
+
+        let result = process_json_args(&json.to_string(), &input_schema).unwrap();
+
+        assert_eq!(result.0.len(), 1);
+        assert_eq!(
+            result.0[0],
+            FuncArg::Array(vec![
+                Felt252::from(u128::MAX),
+                Felt252::from(i128::MIN),
+                Felt252::from(2),     // low limb of "wide"
+                Felt252::from(0x100), // high limb of "wide"
+                Felt252::from(42),    // low limb of "small"
+                Felt252::from(0),     // high limb of "small"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_wide_integer_out_of_range() {
+        let input_schema = r#"
+        schemas:
+            Input:
+                fields:
+                    - request:
+                        type: Primitive
+                        name: u128
+        cairo_input: Input
+        cairo_output: null
+        "#;
+
+        let schema_file = create_temp_file_with_content(input_schema);
+        let input_schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+        let json = json!({"request": "340282366920938463463374607431768211456"});
+
+        let result = process_json_args(&json.to_string(), &input_schema);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Value out of range for u128"));
+    }
+
+    #[test]
+    fn test_positional_params() {
+        let input_schema = r#"
+        schemas:
+            Point:
+                fields:
+                    - x:
+                        type: Primitive
+                        name: u32
+                    - y:
+                        type: Primitive
+                        name: u32
+        enums:
+            Action:
+                variants:
+                    - Idle
+                    - Buy:
+                        type: Primitive
+                        name: u64
+                    - Sell:
+                        type: Primitive
+                        name: u256
+        cairo_input:
+            - a:
+                type: Primitive
+                name: u64
+            - b:
+                type: Array
+                item_type:
+                    type: Struct
+                    name: Point
+            - c:
+                type: Primitive
+                name: ByteArray
+            - d:
+                type: Option
+                item_type:
+                    type: Primitive
+                    name: u32
+            - e:
+                type: Enum
+                name: Action
+        cairo_output: null
+        "#;
+
+        let schema_file = create_temp_file_with_content(input_schema);
+        let input_schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let json = json!({
+            "a": 42,
+            "b": [{"x": 1, "y": 2}, {"x": 3, "y": 4}],
+            "c": "hello",
+            "d": null,
+            "e": {"Buy": 7}
+        });
+
+        let result = process_json_args(&json.to_string(), &input_schema).unwrap();
+
+        assert_eq!(
+            result.0,
+            vec![
+                FuncArg::Single(Felt252::from(42)),
+                FuncArg::Array(vec![
+                    Felt252::from(1),
+                    Felt252::from(2),
+                    Felt252::from(3),
+                    Felt252::from(4),
+                ]),
+                // ByteArray data, pending word and pending word length
+                FuncArg::Array(vec![]),
+                FuncArg::Single(Felt252::from_hex("0x68656c6c6f").unwrap()),
+                FuncArg::Single(Felt252::from(5)),
+                // Option::None selector and padding
+                FuncArg::Single(Felt252::from(1)),
+                FuncArg::Single(Felt252::from(0)),
+                // Action::Buy selector (jump table offset), padding and payload
+                FuncArg::Single(Felt252::from(3)),
+                FuncArg::Single(Felt252::from(0)),
+                FuncArg::Single(Felt252::from(7)),
+            ]
+        );
+    }
+
     #[test]
     fn test_missing_field() {
         let input_schema = r#"
@@ -468,8 +1359,13 @@ mod tests {
         let json = json!({"request": 42});
 
         let result = process_json_args(&json.to_string(), &input_schema);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Missing field: optional"));
+        assert_eq!(
+            result.unwrap_err(),
+            IoSerdeError::MissingField {
+                path: "$".to_string(),
+                field: "optional".to_string()
+            }
+        );
     }
 
     #[test]
@@ -491,7 +1387,10 @@ mod tests {
 
         let result = process_json_args(&json.to_string(), &input_schema);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Expected unsigned integer"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Expected unsigned integer"));
     }
 
     #[test]
@@ -534,7 +1433,10 @@ mod tests {
 
         let result = process_json_args(json, &input_schema);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Failed to parse JSON"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Failed to parse JSON"));
     }
 
     #[test]
@@ -558,6 +1460,7 @@ mod tests {
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
+            .to_string()
             .contains("Expected string for ByteArray"));
     }
 
@@ -582,7 +1485,39 @@ mod tests {
 
         let result = process_json_args(&json.to_string(), &input_schema);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Expected array"));
+        assert!(result.unwrap_err().to_string().contains("Expected array"));
+    }
+
+    #[test]
+    fn test_error_path() {
+        let input_schema = r#"
+        schemas:
+            Point:
+                fields:
+                    - x:
+                        type: Primitive
+                        name: u32
+        cairo_input:
+            - points:
+                type: Array
+                item_type:
+                    type: Struct
+                    name: Point
+        cairo_output: null
+        "#;
+
+        let schema_file = create_temp_file_with_content(input_schema);
+        let input_schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+        let json = json!({"points": [{"x": 1}, {"x": "two"}]});
+
+        let result = process_json_args(&json.to_string(), &input_schema);
+        assert_eq!(
+            result.unwrap_err(),
+            IoSerdeError::TypeMismatch {
+                path: "$.points[1].x".to_string(),
+                message: "Expected unsigned integer for u32".to_string()
+            }
+        );
     }
 
     #[test]
@@ -609,6 +1544,9 @@ mod tests {
 
         let result = process_json_args(&json.to_string(), &input_schema);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Expected unsigned integer"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Expected unsigned integer"));
     }
 }
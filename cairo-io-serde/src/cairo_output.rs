@@ -1,44 +1,97 @@
 use std::{collections::VecDeque, iter::Peekable};
 
 use cairo_lang_sierra::{
-    extensions::core::{CoreLibfunc, CoreType},
+    extensions::{
+        core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+        enm::EnumConcreteType,
+    },
     ids::ConcreteTypeId,
     program::GenericArg,
-    program_registry::ProgramRegistry,
+    program_registry::{ProgramRegistry, ProgramRegistryError},
 };
 use cairo_lang_utils::unordered_hash_map::UnorderedHashMap;
 use cairo_vm::{
-    math_utils::signed_felt, types::relocatable::MaybeRelocatable, vm::vm_core::VirtualMachine,
+    math_utils::signed_felt,
+    types::relocatable::MaybeRelocatable,
+    vm::{errors::memory_errors::MemoryError, vm_core::VirtualMachine},
     Felt252,
 };
+use num_bigint::BigUint;
 use num_traits::cast::ToPrimitive;
 use serde_json::{json, Value};
+use thiserror::Error;
 
-use crate::schema::{Schema, SchemaType};
+use crate::{
+    error::{field_path, index_path, IoSerdeError, ROOT_PATH},
+    schema::{Schema, SchemaType},
+};
 
-pub fn process_output(output: Vec<Felt252>, schema: &Schema) -> Result<String, String> {
+pub fn process_output(output: Vec<Felt252>, schema: &Schema) -> Result<String, IoSerdeError> {
     let schema_name = &schema.cairo_output;
     let mut output_queue: VecDeque<Felt252> = output.into();
 
-    let parsed = parse_schema(&mut output_queue, schema_name, schema)?;
+    let parsed = parse_schema(&mut output_queue, schema_name, schema, ROOT_PATH)?;
+
+    serde_json::to_string_pretty(&parsed).map_err(|e| IoSerdeError::InvalidJson {
+        path: ROOT_PATH.to_string(),
+        message: format!("Failed to serialize to JSON: {}", e),
+    })
+}
+
+fn pop_felt(output_queue: &mut VecDeque<Felt252>, path: &str) -> Result<Felt252, IoSerdeError> {
+    output_queue
+        .pop_front()
+        .ok_or_else(|| IoSerdeError::truncated_output(path))
+}
+
+fn pop_usize(output_queue: &mut VecDeque<Felt252>, path: &str) -> Result<usize, IoSerdeError> {
+    let value = pop_felt(output_queue, path)?;
+    value
+        .to_usize()
+        .ok_or_else(|| IoSerdeError::type_mismatch(path, format!("Invalid length {}", value)))
+}
+
+const BYTES_IN_WORD: usize = 31;
 
-    serde_json::to_string_pretty(&parsed).map_err(|e| format!("Failed to serialize to JSON: {}", e))
+// Returns the `len` least significant bytes of a ByteArray word, in big-endian order
+fn word_bytes(word: &Felt252, len: usize, path: &str) -> Result<Vec<u8>, IoSerdeError> {
+    let bytes = word.to_bytes_be();
+    if len > BYTES_IN_WORD {
+        return Err(IoSerdeError::type_mismatch(
+            path,
+            format!("Invalid ByteArray word length {}", len),
+        ));
+    }
+    let (high, low) = bytes.split_at(bytes.len() - len);
+    if high.iter().any(|byte| *byte != 0) {
+        return Err(IoSerdeError::type_mismatch(
+            path,
+            format!("ByteArray word {} does not fit in {} bytes", word, len),
+        ));
+    }
+    Ok(low.to_vec())
 }
 
 fn parse_schema(
     output_queue: &mut VecDeque<Felt252>,
     schema_name: &str,
     schema: &Schema,
-) -> Result<Value, String> {
+    path: &str,
+) -> Result<Value, IoSerdeError> {
     let schema_def = schema
         .schemas
         .get(schema_name)
-        .ok_or_else(|| format!("Schema {} not found in schema", schema_name))?;
+        .ok_or_else(|| IoSerdeError::unknown_schema(path, format!("Schema {}", schema_name)))?;
 
     let mut result = json!({});
 
     for field in &schema_def.fields {
-        let parsed = parse_value(output_queue, &field.ty, schema)?;
+        let parsed = parse_value(
+            output_queue,
+            &field.ty,
+            schema,
+            &field_path(path, &field.name),
+        )?;
         result[&field.name] = parsed;
     }
 
@@ -49,84 +102,196 @@ fn parse_value(
     output_queue: &mut VecDeque<Felt252>,
     ty: &SchemaType,
     schema: &Schema,
-) -> Result<Value, String> {
+    path: &str,
+) -> Result<Value, IoSerdeError> {
+    let out_of_range =
+        |name: &str| IoSerdeError::type_mismatch(path, format!("Value out of range for {}", name));
+
     match ty {
         SchemaType::Primitive { name } => match name.as_str() {
             "u64" | "u32" | "u16" | "u8" => {
-                let value = output_queue.pop_front().ok_or("Unexpected end of output")?;
-                Ok(json!(value.to_u64()))
+                let value = pop_felt(output_queue, path)?;
+                let num = value.to_u64().ok_or_else(|| out_of_range(name))?;
+                Ok(json!(num))
             }
             "i64" | "i32" | "i16" | "i8" => {
-                let value = output_queue.pop_front().ok_or("Unexpected end of output")?;
-                Ok(json!(signed_felt(value).to_i64()))
+                let value = pop_felt(output_queue, path)?;
+                let num = signed_felt(value)
+                    .to_i64()
+                    .ok_or_else(|| out_of_range(name))?;
+                Ok(json!(num))
+            }
+            "u128" => {
+                let value = pop_felt(output_queue, path)?;
+                let num = value.to_u128().ok_or_else(|| out_of_range(name))?;
+                Ok(json!(num.to_string()))
+            }
+            "i128" => {
+                let value = pop_felt(output_queue, path)?;
+                let num = signed_felt(value)
+                    .to_i128()
+                    .ok_or_else(|| out_of_range(name))?;
+                Ok(json!(num.to_string()))
+            }
+            "u256" => {
+                let low = pop_felt(output_queue, path)?;
+                let high = pop_felt(output_queue, path)?;
+                // u256 is serialized as its low and high 128-bit limbs
+                let (low, high) = low
+                    .to_u128()
+                    .zip(high.to_u128())
+                    .ok_or_else(|| out_of_range(name))?;
+                let num = (BigUint::from(high) << 128u32) + low;
+                Ok(json!(num.to_string()))
             }
             "F64" => {
-                let value = output_queue.pop_front().ok_or("Unexpected end of output")?;
-                let float_value = (value.to_i64().unwrap() as f64) / 2f64.powi(32);
+                let value = pop_felt(output_queue, path)?;
+                let num = signed_felt(value)
+                    .to_i64()
+                    .ok_or_else(|| out_of_range(name))?;
+                let float_value = (num as f64) / 2f64.powi(32);
                 Ok(json!(float_value))
             }
             "felt252" => {
-                let value = output_queue.pop_front().ok_or("Unexpected end of output")?;
+                let value = pop_felt(output_queue, path)?;
                 Ok(json!(value.to_hex_string()))
             }
             "ByteArray" => {
-                let length = output_queue
-                    .pop_front()
-                    .ok_or("Unexpected end of output")?
-                    .to_usize()
-                    .unwrap();
+                // Serialized as the number of full bytes31 words, the words, the pending word and its length
+                let words_count = pop_usize(output_queue, path)?;
                 let mut bytes = Vec::new();
-                for _ in 0..length {
-                    let byte = output_queue.pop_front().ok_or("Unexpected end of output")?;
-                    bytes.push(byte.to_u8().unwrap());
-                }
-                let pending_word = output_queue.pop_front().ok_or("Unexpected end of output")?;
-                let pending_word_len =
-                    output_queue.pop_front().ok_or("Unexpected end of output")?;
-
-                let mut result = String::from_utf8(bytes)
-                    .map_err(|e| format!("Invalid UTF-8 sequence: {}", e))?;
-                if pending_word_len.to_usize().unwrap() > 0 {
-                    result.push_str(&pending_word.to_string());
+                for _ in 0..words_count {
+                    let word = pop_felt(output_queue, path)?;
+                    bytes.extend(word_bytes(&word, BYTES_IN_WORD, path)?);
                 }
+                let pending_word = pop_felt(output_queue, path)?;
+                let pending_word_len = pop_usize(output_queue, path)?;
+                bytes.extend(word_bytes(&pending_word, pending_word_len, path)?);
+
+                let result = String::from_utf8(bytes).map_err(|e| {
+                    IoSerdeError::type_mismatch(path, format!("Invalid UTF-8 sequence: {}", e))
+                })?;
 
                 Ok(json!(result))
             }
             "bool" => {
-                let value = output_queue.pop_front().ok_or("Unexpected end of output")?;
+                let value = pop_felt(output_queue, path)?;
                 Ok(json!(value != Felt252::ZERO))
             }
-            _ => Err(format!("Unknown primitive type: {}", name)),
+            _ => Err(IoSerdeError::unknown_schema(
+                path,
+                format!("Primitive type {}", name),
+            )),
         },
         SchemaType::Array { item_type } | SchemaType::Span { item_type } => {
-            let length = output_queue
-                .pop_front()
-                .ok_or("Unexpected end of output")?
+            let length = pop_usize(output_queue, path)?;
+            let mut result = Vec::new();
+            for i in 0..length {
+                let parsed = parse_value(output_queue, item_type, schema, &index_path(path, i))?;
+                result.push(parsed);
+            }
+            Ok(json!(result))
+        }
+        SchemaType::Struct { name } => parse_schema(output_queue, name, schema, path),
+        SchemaType::Tensor { item_type } => {
+            let shape = parse_value(
+                output_queue,
+                &SchemaType::Span {
+                    item_type: Box::new(SchemaType::Primitive {
+                        name: "u32".to_string(),
+                    }),
+                },
+                schema,
+                &field_path(path, "shape"),
+            )?;
+            let data = parse_value(
+                output_queue,
+                &SchemaType::Span {
+                    item_type: item_type.clone(),
+                },
+                schema,
+                &field_path(path, "data"),
+            )?;
+            Ok(json!({ "shape": shape, "data": data }))
+        }
+        SchemaType::Enum { name } => {
+            let enum_def = schema
+                .enums
+                .get(name)
+                .ok_or_else(|| IoSerdeError::unknown_schema(path, format!("Enum {}", name)))?;
+            let variant_idx = pop_felt(output_queue, path)?;
+            let variant = variant_idx
                 .to_usize()
-                .unwrap();
+                .and_then(|idx| enum_def.variants.get(idx))
+                .ok_or_else(|| {
+                    IoSerdeError::type_mismatch(
+                        path,
+                        format!("Invalid variant index {} for enum {}", variant_idx, name),
+                    )
+                })?;
+            match &variant.ty {
+                Some(ty) => {
+                    let mut result = json!({});
+                    result[&variant.name] =
+                        parse_value(output_queue, ty, schema, &field_path(path, &variant.name))?;
+                    Ok(result)
+                }
+                None => Ok(json!(variant.name)),
+            }
+        }
+        SchemaType::Option { item_type } => {
+            // Option<T> is serialized as an enum with variants Some(T) = 0 and None = 1
+            let variant_idx = pop_felt(output_queue, path)?;
+            if variant_idx == Felt252::ZERO {
+                parse_value(output_queue, item_type, schema, path)
+            } else if variant_idx == Felt252::ONE {
+                Ok(Value::Null)
+            } else {
+                Err(IoSerdeError::type_mismatch(
+                    path,
+                    format!("Invalid variant index {} for Option", variant_idx),
+                ))
+            }
+        }
+        SchemaType::Tuple { items } => {
             let mut result = Vec::new();
-            for _ in 0..length {
-                let parsed = parse_value(output_queue, item_type, schema)?;
+            for (i, item_type) in items.iter().enumerate() {
+                let parsed = parse_value(output_queue, item_type, schema, &index_path(path, i))?;
                 result.push(parsed);
             }
             Ok(json!(result))
         }
-        SchemaType::Struct { name } => parse_schema(output_queue, name, schema),
     }
 }
 
+#[derive(Debug, Error)]
+pub enum SerializeOutputError {
+    #[error("Unsupported return type: {0}")]
+    UnsupportedType(ConcreteTypeId),
+    #[error("Missing return value for type {0}")]
+    MissingReturnValue(ConcreteTypeId),
+    #[error("Malformed return value for type {0}")]
+    MalformedValue(ConcreteTypeId),
+    #[error("No size for concrete type id: {0}")]
+    NoTypeSizeForId(ConcreteTypeId),
+    #[error(transparent)]
+    ProgramRegistry(#[from] Box<ProgramRegistryError>),
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+}
+
 pub fn serialize_output(
     return_values: &[MaybeRelocatable],
     vm: &mut VirtualMachine,
     return_type_id: Option<&ConcreteTypeId>,
     sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     type_sizes: &UnorderedHashMap<ConcreteTypeId, i16>,
-) -> Vec<Felt252> {
+) -> Result<Vec<Felt252>, SerializeOutputError> {
     let mut output_vec = Vec::new();
     let return_type_id = if let Some(id) = return_type_id {
         id
     } else {
-        return output_vec;
+        return Ok(output_vec);
     };
     let mut return_values_iter = return_values.iter().peekable();
     serialize_output_inner(
@@ -136,9 +301,57 @@ pub fn serialize_output(
         return_type_id,
         sierra_program_registry,
         type_sizes,
-    );
+    )?;
 
-    output_vec
+    // A returned `Array<felt252>` already holds the Serde-encoded output, so its values are
+    // decoded with the schema as they are, without the length of the array
+    if is_felt252_array(return_type_id, sierra_program_registry)? {
+        output_vec.remove(0);
+    }
+
+    Ok(output_vec)
+}
+
+// Returns whether the return type is an `Array<felt252>`, possibly returned in a `PanicResult`
+fn is_felt252_array(
+    return_type_id: &ConcreteTypeId,
+    sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+) -> Result<bool, SerializeOutputError> {
+    match sierra_program_registry.get_type(return_type_id)? {
+        CoreTypeConcrete::Array(info) => Ok(matches!(
+            sierra_program_registry.get_type(&info.ty)?,
+            CoreTypeConcrete::Felt252(_)
+        )),
+        // The Ok variant holds the returned value in a single-element tuple
+        CoreTypeConcrete::Enum(info) if is_panic_result(info) => {
+            match sierra_program_registry.get_type(&info.variants[0])? {
+                CoreTypeConcrete::Struct(tuple) if tuple.members.len() == 1 => {
+                    is_felt252_array(&tuple.members[0], sierra_program_registry)
+                }
+                _ => Ok(false),
+            }
+        }
+        _ => Ok(false),
+    }
+}
+
+fn is_panic_result(info: &EnumConcreteType) -> bool {
+    match &info.info.long_id.generic_args[0] {
+        GenericArg::UserType(user_type) => user_type
+            .debug_name
+            .as_ref()
+            .is_some_and(|n| n.starts_with("core::panics::PanicResult")),
+        _ => false,
+    }
+}
+
+fn next_value<'a>(
+    return_values_iter: &mut impl Iterator<Item = &'a MaybeRelocatable>,
+    return_type_id: &ConcreteTypeId,
+) -> Result<&'a MaybeRelocatable, SerializeOutputError> {
+    return_values_iter
+        .next()
+        .ok_or_else(|| SerializeOutputError::MissingReturnValue(return_type_id.clone()))
 }
 
 fn serialize_output_inner<'a>(
@@ -148,25 +361,35 @@ fn serialize_output_inner<'a>(
     return_type_id: &ConcreteTypeId,
     sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
     type_sizes: &UnorderedHashMap<ConcreteTypeId, i16>,
-) {
-    match sierra_program_registry.get_type(return_type_id).unwrap() {
-        cairo_lang_sierra::extensions::core::CoreTypeConcrete::Array(info) => {
+) -> Result<(), SerializeOutputError> {
+    let type_size = |ty: &ConcreteTypeId| {
+        type_sizes
+            .get(ty)
+            .map(|size| *size as usize)
+            .ok_or_else(|| SerializeOutputError::NoTypeSizeForId(ty.clone()))
+    };
+
+    match sierra_program_registry.get_type(return_type_id)? {
+        CoreTypeConcrete::Array(info) => {
             // Fetch array from memory
-            let array_start = return_values_iter
-                .next()
-                .expect("Missing return value")
+            let array_start = next_value(return_values_iter, return_type_id)?
                 .get_relocatable()
-                .expect("Array start_ptr not Relocatable");
-            let array_end = return_values_iter
-                .next()
-                .expect("Missing return value")
+                .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
+            let array_end = next_value(return_values_iter, return_type_id)?
                 .get_relocatable()
-                .expect("Array end_ptr not Relocatable");
-            let array_size = (array_end - array_start).unwrap();
+                .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
+            let array_size = (array_end - array_start)
+                .map_err(|_| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
 
-            let array_data = vm.get_continuous_range(array_start, array_size).unwrap();
-            let mut array_data_iter = array_data.iter().peekable();
             let array_elem_id = &info.ty;
+            let elem_size = type_size(array_elem_id)?;
+            // Cairo Serde encodes the array length ahead of its elements
+            output_vec.push(Felt252::from(
+                array_size.checked_div(elem_size).unwrap_or(0),
+            ));
+
+            let array_data = vm.get_continuous_range(array_start, array_size)?;
+            let mut array_data_iter = array_data.iter().peekable();
             // Serialize array data
             while array_data_iter.peek().is_some() {
                 serialize_output_inner(
@@ -176,65 +399,151 @@ fn serialize_output_inner<'a>(
                     array_elem_id,
                     sierra_program_registry,
                     type_sizes,
-                )
+                )?;
             }
         }
-        cairo_lang_sierra::extensions::core::CoreTypeConcrete::Felt252(_) => {
-            let val = return_values_iter
-                .next()
-                .expect("Missing return value")
+        CoreTypeConcrete::Felt252(_)
+        | CoreTypeConcrete::Bytes31(_)
+        | CoreTypeConcrete::BoundedInt(_)
+        | CoreTypeConcrete::Uint8(_)
+        | CoreTypeConcrete::Uint16(_)
+        | CoreTypeConcrete::Uint32(_)
+        | CoreTypeConcrete::Uint64(_)
+        | CoreTypeConcrete::Uint128(_)
+        | CoreTypeConcrete::Sint8(_)
+        | CoreTypeConcrete::Sint16(_)
+        | CoreTypeConcrete::Sint32(_)
+        | CoreTypeConcrete::Sint64(_)
+        | CoreTypeConcrete::Sint128(_) => {
+            let val = next_value(return_values_iter, return_type_id)?
                 .get_int()
-                .expect("Value is not an integer");
+                .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
             output_vec.push(val);
         }
-        cairo_lang_sierra::extensions::core::CoreTypeConcrete::Enum(info) => {
-            // First we check if it is a Panic enum, as we already handled panics when fetching return values,
-            // we can ignore them and move on to the non-panic variant
-            if let GenericArg::UserType(user_type) = &info.info.long_id.generic_args[0] {
-                if user_type
-                    .debug_name
-                    .as_ref()
-                    .is_some_and(|n| n.starts_with("core::panics::PanicResult"))
-                {
-                    return serialize_output_inner(
-                        return_values_iter,
+        // These wrappers share the memory representation of their inner type
+        CoreTypeConcrete::Snapshot(info) | CoreTypeConcrete::NonZero(info) => {
+            serialize_output_inner(
+                return_values_iter,
+                output_vec,
+                vm,
+                &info.ty,
+                sierra_program_registry,
+                type_sizes,
+            )?;
+        }
+        CoreTypeConcrete::Box(info) => {
+            // A box holds a pointer to its inner value
+            let ptr = next_value(return_values_iter, return_type_id)?
+                .get_relocatable()
+                .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
+            let inner_data = vm.get_continuous_range(ptr, type_size(&info.ty)?)?;
+            serialize_output_inner(
+                &mut inner_data.iter().peekable(),
+                output_vec,
+                vm,
+                &info.ty,
+                sierra_program_registry,
+                type_sizes,
+            )?;
+        }
+        CoreTypeConcrete::Nullable(info) => {
+            // A nullable holds either 0 (null) or a pointer to its inner value,
+            // it is serialized as an Option: Some(T) = 0 and None = 1
+            match next_value(return_values_iter, return_type_id)? {
+                MaybeRelocatable::RelocatableValue(ptr) => {
+                    output_vec.push(Felt252::ZERO);
+                    let inner_data = vm.get_continuous_range(*ptr, type_size(&info.ty)?)?;
+                    serialize_output_inner(
+                        &mut inner_data.iter().peekable(),
                         output_vec,
                         vm,
-                        &info.variants[0],
+                        &info.ty,
                         sierra_program_registry,
                         type_sizes,
-                    );
+                    )?;
+                }
+                MaybeRelocatable::Int(value) if value == &Felt252::ZERO => {
+                    output_vec.push(Felt252::ONE);
+                }
+                MaybeRelocatable::Int(_) => {
+                    return Err(SerializeOutputError::MalformedValue(return_type_id.clone()))
                 }
             }
+        }
+        CoreTypeConcrete::SquashedFelt252Dict(info) => {
+            // A squashed dict points to its (key, previous value, new value) entries,
+            // it is serialized as an array of (key, value) pairs
+            let dict_start = next_value(return_values_iter, return_type_id)?
+                .get_relocatable()
+                .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
+            let dict_end = next_value(return_values_iter, return_type_id)?
+                .get_relocatable()
+                .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
+            let dict_size = (dict_end - dict_start)
+                .map_err(|_| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
+            let entries = vm.get_continuous_range(dict_start, dict_size)?;
+            output_vec.push(Felt252::from(entries.len() / 3));
+            for entry in entries.chunks(3) {
+                let [key, _prev_value, new_value] = entry else {
+                    return Err(SerializeOutputError::MalformedValue(return_type_id.clone()));
+                };
+                let key = key
+                    .get_int()
+                    .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
+                output_vec.push(key);
+                serialize_output_inner(
+                    &mut [new_value.clone()].iter().peekable(),
+                    output_vec,
+                    vm,
+                    &info.ty,
+                    sierra_program_registry,
+                    type_sizes,
+                )?;
+            }
+        }
+        CoreTypeConcrete::Enum(info) => {
+            // First we check if it is a Panic enum, as we already handled panics when fetching return values,
+            // we can ignore them and move on to the non-panic variant
+            if is_panic_result(info) {
+                return serialize_output_inner(
+                    return_values_iter,
+                    output_vec,
+                    vm,
+                    &info.variants[0],
+                    sierra_program_registry,
+                    type_sizes,
+                );
+            }
             let num_variants = &info.variants.len();
-            let casm_variant_idx: usize = return_values_iter
-                .next()
-                .expect("Missing return value")
+            let casm_variant_idx: usize = next_value(return_values_iter, return_type_id)?
                 .get_int()
-                .expect("Enum tag is not integer")
-                .to_usize()
-                .expect("Invalid enum tag");
+                .and_then(|tag| tag.to_usize())
+                .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
             // Convert casm variant idx to sierra variant idx
             let variant_idx = if *num_variants > 2 {
-                num_variants - 1 - (casm_variant_idx >> 1)
+                num_variants
+                    .checked_sub(1 + (casm_variant_idx >> 1))
+                    .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?
             } else {
                 casm_variant_idx
             };
-            let variant_type_id = &info.variants[variant_idx];
+            let variant_type_id = info
+                .variants
+                .get(variant_idx)
+                .ok_or_else(|| SerializeOutputError::MalformedValue(return_type_id.clone()))?;
+            // Cairo Serde encodes the variant index ahead of its payload
+            output_vec.push(Felt252::from(variant_idx));
 
             // Space is always allocated for the largest enum member, padding with zeros in front for the smaller variants
             let mut max_variant_size = 0;
             for variant in &info.variants {
-                let variant_size = type_sizes.get(variant).unwrap();
-                max_variant_size = std::cmp::max(max_variant_size, *variant_size)
+                max_variant_size = std::cmp::max(max_variant_size, type_size(variant)?)
             }
-            for _ in 0..max_variant_size - type_sizes.get(variant_type_id).unwrap() {
+            for _ in 0..max_variant_size - type_size(variant_type_id)? {
                 // Remove padding
-                assert_eq!(
-                    return_values_iter.next(),
-                    Some(&MaybeRelocatable::from(0)),
-                    "Malformed enum"
-                );
+                if next_value(return_values_iter, return_type_id)? != &MaybeRelocatable::from(0) {
+                    return Err(SerializeOutputError::MalformedValue(return_type_id.clone()));
+                }
             }
             serialize_output_inner(
                 return_values_iter,
@@ -243,9 +552,9 @@ fn serialize_output_inner<'a>(
                 variant_type_id,
                 sierra_program_registry,
                 type_sizes,
-            )
+            )?;
         }
-        cairo_lang_sierra::extensions::core::CoreTypeConcrete::Struct(info) => {
+        CoreTypeConcrete::Struct(info) => {
             for member_type_id in &info.members {
                 serialize_output_inner(
                     return_values_iter,
@@ -254,17 +563,27 @@ fn serialize_output_inner<'a>(
                     member_type_id,
                     sierra_program_registry,
                     type_sizes,
-                )
+                )?;
             }
         }
-        _ => panic!("Unexpected return type"),
+        _ => {
+            return Err(SerializeOutputError::UnsupportedType(
+                return_type_id.clone(),
+            ))
+        }
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::schema::parse_schema_file;
+    use crate::{
+        cairo_input::{process_json_args, serialize_json_args},
+        schema::parse_schema_file,
+        schema_inference::infer_schema,
+        FuncArg,
+    };
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -387,22 +706,9 @@ mod tests {
         let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
 
         let output = vec![
-            Felt252::from(13),  // Length of the byte array
-            Felt252::from(72),  // 'H'
-            Felt252::from(101), // 'e'
-            Felt252::from(108), // 'l'
-            Felt252::from(108), // 'l'
-            Felt252::from(111), // 'o'
-            Felt252::from(44),  // ','
-            Felt252::from(32),  // ' '
-            Felt252::from(87),  // 'W'
-            Felt252::from(111), // 'o'
-            Felt252::from(114), // 'r'
-            Felt252::from(108), // 'l'
-            Felt252::from(100), // 'd'
-            Felt252::from(33),  // '!'
-            Felt252::from(0),   // Pending word
-            Felt252::from(0),   // Pending word length
+            Felt252::from(0),                                           // Number of full words
+            Felt252::from_hex("0x48656c6c6f2c20576f726c6421").unwrap(), // Pending word
+            Felt252::from(13),                                          // Pending word length
         ];
 
         let result = process_output(output, &schema).unwrap();
@@ -411,6 +717,252 @@ mod tests {
         assert_eq!(parsed["byte_array"], "Hello, World!");
     }
 
+    fn byte_array_round_trip(string: &str) -> Value {
+        let schema_content = r#"
+        schemas:
+            Message:
+                fields:
+                    - text:
+                        type: Primitive
+                        name: ByteArray
+        cairo_input: Message
+        cairo_output: Message
+        "#;
+
+        let schema_file = create_temp_file_with_content(schema_content);
+        let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let args = process_json_args(&json!({ "text": string }).to_string(), &schema).unwrap();
+        let FuncArg::Array(serialized) = &args.0[0] else {
+            panic!("Expected the input to be serialized into an array");
+        };
+        let result = process_output(serialized.clone(), &schema).unwrap();
+        serde_json::from_str::<Value>(&result).unwrap()["text"].clone()
+    }
+
+    #[test]
+    fn test_byte_array_round_trip() {
+        let short = "Hello";
+        let exact = "abcdefghijklmnopqrstuvwxyz01234";
+        let long = "A string that spans more than one bytes31 word, with ünïcödé across words";
+        assert_eq!(exact.len(), 31);
+
+        assert_eq!(byte_array_round_trip(""), "");
+        assert_eq!(byte_array_round_trip(short), short);
+        assert_eq!(byte_array_round_trip(exact), exact);
+        assert_eq!(byte_array_round_trip(long), long);
+    }
+
+    #[test]
+    fn test_serialize_json_args_params() {
+        let schema_content = r#"
+        schemas:
+            Output:
+                fields:
+                    - n:
+                        type: Primitive
+                        name: u32
+                    - values:
+                        type: Array
+                        item_type:
+                            type: Primitive
+                            name: u32
+        cairo_input:
+            - n:
+                type: Primitive
+                name: u32
+            - values:
+                type: Array
+                item_type:
+                    type: Primitive
+                    name: u32
+        cairo_output: Output
+        "#;
+
+        let schema_file = create_temp_file_with_content(schema_content);
+        let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let args = json!({ "n": 7, "values": [1, 2] });
+        let serialized = serialize_json_args(&args.to_string(), &schema).unwrap();
+        assert_eq!(
+            serialized,
+            [7, 2, 1, 2].map(Felt252::from).to_vec(),
+            "Expected the params to be serialized one after the other"
+        );
+
+        // The output has the same fields, so it is read back from the same felts
+        let result = process_output(serialized, &schema).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&result).unwrap(), args);
+    }
+
+    #[test]
+    fn test_process_output_tensor() {
+        let schema_content = r#"
+        schemas:
+            Output:
+                fields:
+                    - tensor:
+                        type: Tensor
+                        item_type:
+                            type: Primitive
+                            name: i32
+        cairo_input: null
+        cairo_output: Output
+        "#;
+
+        let schema_file = create_temp_file_with_content(schema_content);
+        let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let output = vec![
+            Felt252::from(2), // Length of the shape
+            Felt252::from(2),
+            Felt252::from(2),
+            Felt252::from(4), // Length of the data
+            Felt252::from(1),
+            Felt252::from(-2),
+            Felt252::from(3),
+            Felt252::from(-4),
+        ];
+
+        let result = process_output(output, &schema).unwrap();
+        let parsed: Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(parsed["tensor"]["shape"], json!([2, 2]));
+        assert_eq!(parsed["tensor"]["data"], json!([1, -2, 3, -4]));
+    }
+
+    #[test]
+    fn test_process_output_enum_option_tuple() {
+        let schema_content = r#"
+        schemas:
+            Output:
+                fields:
+                    - action:
+                        type: Enum
+                        name: Action
+                    - idle:
+                        type: Enum
+                        name: Action
+                    - some:
+                        type: Option
+                        item_type:
+                            type: Primitive
+                            name: u32
+                    - none:
+                        type: Option
+                        item_type:
+                            type: Primitive
+                            name: u32
+                    - pair:
+                        type: Tuple
+                        items:
+                            - type: Primitive
+                              name: u32
+                            - type: Primitive
+                              name: bool
+        enums:
+            Action:
+                variants:
+                    - Idle
+                    - Buy:
+                        type: Primitive
+                        name: u64
+        cairo_input: null
+        cairo_output: Output
+        "#;
+
+        let schema_file = create_temp_file_with_content(schema_content);
+        let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let output = vec![
+            Felt252::from(1),  // Action::Buy
+            Felt252::from(10), // Buy payload
+            Felt252::from(0),  // Action::Idle
+            Felt252::from(0),  // Option::Some
+            Felt252::from(7),  // Some payload
+            Felt252::from(1),  // Option::None
+            Felt252::from(3),  // Tuple first item
+            Felt252::from(1),  // Tuple second item
+        ];
+
+        let result = process_output(output, &schema).unwrap();
+        let parsed: Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(parsed["action"], json!({"Buy": 10}));
+        assert_eq!(parsed["idle"], "Idle");
+        assert_eq!(parsed["some"], 7);
+        assert_eq!(parsed["none"], Value::Null);
+        assert_eq!(parsed["pair"], json!([3, true]));
+    }
+
+    #[test]
+    fn test_invalid_enum_variant() {
+        let schema_content = r#"
+        schemas:
+            Output:
+                fields:
+                    - action:
+                        type: Enum
+                        name: Action
+        enums:
+            Action:
+                variants:
+                    - Idle
+        cairo_input: null
+        cairo_output: Output
+        "#;
+
+        let schema_file = create_temp_file_with_content(schema_content);
+        let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let result = process_output(vec![Felt252::from(3)], &schema);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid variant index 3 for enum Action"));
+    }
+
+    #[test]
+    fn test_process_output_wide_integers() {
+        let schema_content = r#"
+        schemas:
+            Output:
+                fields:
+                    - unsigned:
+                        type: Primitive
+                        name: u128
+                    - signed:
+                        type: Primitive
+                        name: i128
+                    - wide:
+                        type: Primitive
+                        name: u256
+        cairo_input: null
+        cairo_output: Output
+        "#;
+
+        let schema_file = create_temp_file_with_content(schema_content);
+        let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let output = vec![
+            Felt252::from(u128::MAX),
+            Felt252::from(-42),
+            Felt252::from(2),     // low limb
+            Felt252::from(0x100), // high limb
+        ];
+
+        let result = process_output(output, &schema).unwrap();
+        let parsed: Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(
+            parsed["unsigned"],
+            "340282366920938463463374607431768211455"
+        );
+        assert_eq!(parsed["signed"], "-42");
+        assert_eq!(parsed["wide"], "87112285931760246646623899502532662132738");
+    }
+
     #[test]
     fn test_insufficient_output_data() {
         let schema_content = r#"
@@ -430,8 +982,12 @@ mod tests {
         let output = vec![]; // Empty output
 
         let result = process_output(output, &schema);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Unexpected end of output"));
+        assert_eq!(
+            result.unwrap_err(),
+            IoSerdeError::TruncatedOutput {
+                path: "$.value".to_string()
+            }
+        );
     }
 
     #[test]
@@ -453,10 +1009,13 @@ mod tests {
         let output = vec![Felt252::from(42)];
 
         let result = process_output(output, &schema);
-        assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .contains("Unknown primitive type: invalid_type"));
+        assert_eq!(
+            result.unwrap_err(),
+            IoSerdeError::UnknownSchema {
+                path: "$.value".to_string(),
+                name: "Primitive type invalid_type".to_string()
+            }
+        );
     }
 
     #[test]
@@ -480,8 +1039,12 @@ mod tests {
         let output = vec![Felt252::from(3), Felt252::from(1), Felt252::from(2)]; // Declared length 3, but only 2 elements
 
         let result = process_output(output, &schema);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Unexpected end of output"));
+        assert_eq!(
+            result.unwrap_err(),
+            IoSerdeError::TruncatedOutput {
+                path: "$.array[2]".to_string()
+            }
+        );
     }
 
     #[test]
@@ -501,16 +1064,17 @@ mod tests {
         let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
 
         let output = vec![
-            Felt252::from(2),   // Length
-            Felt252::from(255), // Invalid UTF-8 byte
-            Felt252::from(255), // Invalid UTF-8 byte
-            Felt252::from(0),   // Pending word
-            Felt252::from(0),   // Pending word length
+            Felt252::from(0),    // Number of full words
+            Felt252::from(0xff), // Invalid UTF-8 byte
+            Felt252::from(1),    // Pending word length
         ];
 
         let result = process_output(output, &schema);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Invalid UTF-8 sequence"));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid UTF-8 sequence"));
     }
 
     #[test]
@@ -535,6 +1099,250 @@ mod tests {
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
+            .to_string()
             .contains("Schema MissingStruct not found in schema"));
     }
+
+    fn serialize_test_output(
+        sierra: &str,
+        return_type: &str,
+        type_sizes: &[(&str, i16)],
+        return_values: impl FnOnce(&mut VirtualMachine) -> Vec<MaybeRelocatable>,
+    ) -> Result<Vec<Felt252>, SerializeOutputError> {
+        let program = cairo_lang_sierra::ProgramParser::new()
+            .parse(sierra)
+            .unwrap();
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let type_sizes = type_sizes
+            .iter()
+            .map(|(ty, size)| (ConcreteTypeId::from_string(*ty), *size))
+            .collect::<UnorderedHashMap<_, _>>();
+        let mut vm = VirtualMachine::new(false);
+        let return_values = return_values(&mut vm);
+        serialize_output(
+            &return_values,
+            &mut vm,
+            Some(&ConcreteTypeId::from_string(return_type)),
+            &registry,
+            &type_sizes,
+        )
+    }
+
+    #[test]
+    fn test_serialize_output_wrapper_types() {
+        let sierra = r#"
+            type u8 = u8;
+            type u16 = u16;
+            type u32 = u32;
+            type Box<u32> = Box<u32>;
+            type Nullable<u32> = Nullable<u32>;
+            type Array<u16> = Array<u16>;
+            type Snapshot<Array<u16>> = Snapshot<Array<u16>>;
+            type Output = Struct<ut@Tuple, u8, Box<u32>, Nullable<u32>, Nullable<u32>, Snapshot<Array<u16>>>;
+        "#;
+        let type_sizes = [
+            ("u8", 1),
+            ("u16", 1),
+            ("u32", 1),
+            ("Box<u32>", 1),
+            ("Nullable<u32>", 1),
+            ("Array<u16>", 2),
+            ("Snapshot<Array<u16>>", 2),
+            ("Output", 7),
+        ];
+
+        let result = serialize_test_output(sierra, "Output", &type_sizes, |vm| {
+            let boxed = vm.add_memory_segment();
+            vm.load_data(boxed, &[MaybeRelocatable::from(5)]).unwrap();
+            let nullable = vm.add_memory_segment();
+            vm.load_data(nullable, &[MaybeRelocatable::from(6)])
+                .unwrap();
+            let array_start = vm.add_memory_segment();
+            let array_end = vm
+                .load_data(
+                    array_start,
+                    &[
+                        MaybeRelocatable::from(1),
+                        MaybeRelocatable::from(2),
+                        MaybeRelocatable::from(3),
+                    ],
+                )
+                .unwrap();
+            vec![
+                MaybeRelocatable::from(7),
+                MaybeRelocatable::from(boxed),
+                MaybeRelocatable::from(nullable),
+                MaybeRelocatable::from(0),
+                MaybeRelocatable::from(array_start),
+                MaybeRelocatable::from(array_end),
+            ]
+        })
+        .unwrap();
+
+        assert_eq!(
+            result,
+            [7, 5, 0, 6, 1, 3, 1, 2, 3]
+                .into_iter()
+                .map(Felt252::from)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn load_array(vm: &mut VirtualMachine, values: &[i32]) -> Vec<MaybeRelocatable> {
+        let array_start = vm.add_memory_segment();
+        let values = values
+            .iter()
+            .map(|value| MaybeRelocatable::from(Felt252::from(*value)))
+            .collect::<Vec<_>>();
+        let array_end = vm.load_data(array_start, &values).unwrap();
+        vec![
+            MaybeRelocatable::from(array_start),
+            MaybeRelocatable::from(array_end),
+        ]
+    }
+
+    #[test]
+    fn test_serialize_output_felt252_array_is_raw() {
+        // Functions returning an `Array<felt252>` return their output already serialized,
+        // so the schema decodes the values of the array, without its length
+        let schema_content = r#"
+        schemas:
+            Output:
+                fields:
+                    - a:
+                        type: Primitive
+                        name: u32
+                    - b:
+                        type: Primitive
+                        name: u32
+        cairo_input: null
+        cairo_output: Output
+        "#;
+        let schema_file = create_temp_file_with_content(schema_content);
+        let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let sierra = r#"
+            type felt252 = felt252;
+            type Array<felt252> = Array<felt252>;
+        "#;
+        let serialized = serialize_test_output(
+            sierra,
+            "Array<felt252>",
+            &[("felt252", 1), ("Array<felt252>", 2)],
+            |vm| load_array(vm, &[4, 2]),
+        )
+        .unwrap();
+        assert_eq!(serialized, vec![Felt252::from(4), Felt252::from(2)]);
+
+        let result = process_output(serialized, &schema).unwrap();
+        let parsed: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(parsed, json!({"a": 4, "b": 2}));
+    }
+
+    #[test]
+    fn test_serialize_output_panic_result_felt252_array_is_raw() {
+        let sierra = r#"
+            type felt252 = felt252;
+            type Array<felt252> = Array<felt252>;
+            type core::panics::Panic = Struct<ut@core::panics::Panic>;
+            type Tuple<Array<felt252>> = Struct<ut@Tuple, Array<felt252>>;
+            type Tuple<core::panics::Panic, Array<felt252>> = Struct<ut@Tuple, core::panics::Panic, Array<felt252>>;
+            type PanicResult = Enum<ut@core::panics::PanicResult::<(core::array::Array::<core::felt252>,)>, Tuple<Array<felt252>>, Tuple<core::panics::Panic, Array<felt252>>>;
+        "#;
+        let type_sizes = [
+            ("felt252", 1),
+            ("Array<felt252>", 2),
+            ("core::panics::Panic", 0),
+            ("Tuple<Array<felt252>>", 2),
+            ("Tuple<core::panics::Panic, Array<felt252>>", 2),
+            ("PanicResult", 3),
+        ];
+
+        let serialized = serialize_test_output(sierra, "PanicResult", &type_sizes, |vm| {
+            load_array(vm, &[1, 2, 3])
+        })
+        .unwrap();
+
+        assert_eq!(
+            serialized,
+            [1, 2, 3].into_iter().map(Felt252::from).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_serialize_output_typed_array_has_length() {
+        let sierra = r#"
+            type u32 = u32;
+            type Array<u32> = Array<u32>;
+        "#;
+
+        let serialized = serialize_test_output(
+            sierra,
+            "Array<u32>",
+            &[("u32", 1), ("Array<u32>", 2)],
+            |vm| load_array(vm, &[7, 8]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            serialized,
+            [2, 7, 8].into_iter().map(Felt252::from).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_serialize_output_with_inferred_schema() {
+        let sierra = r#"
+            type felt252 = felt252;
+            type u32 = u32;
+            type Array<felt252> = Array<felt252>;
+
+            return([0]);
+
+            agent::raw@0() -> (Array<felt252>);
+            agent::typed@0() -> (u32);
+        "#;
+        let program = cairo_lang_sierra::ProgramParser::new()
+            .parse(sierra)
+            .unwrap();
+
+        // A returned `Array<felt252>` is the serialized output already, it gets no output
+        // schema and its values are kept as they are
+        let schema = infer_schema(&program, "::raw").unwrap();
+        assert!(!schema.has_output());
+        let serialized = serialize_test_output(
+            sierra,
+            "Array<felt252>",
+            &[("felt252", 1), ("Array<felt252>", 2)],
+            |vm| load_array(vm, &[4, 2]),
+        )
+        .unwrap();
+        assert_eq!(serialized, vec![Felt252::from(4), Felt252::from(2)]);
+
+        // Other return values are decoded with the inferred schema
+        let schema = infer_schema(&program, "::typed").unwrap();
+        let serialized = serialize_test_output(sierra, "u32", &[("u32", 1)], |_| {
+            vec![MaybeRelocatable::from(7)]
+        })
+        .unwrap();
+        let result = process_output(serialized, &schema).unwrap();
+        let parsed: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(parsed, json!({"value": 7}));
+    }
+
+    #[test]
+    fn test_serialize_output_unsupported_type() {
+        let sierra = r#"
+            type RangeCheck = RangeCheck;
+        "#;
+
+        let result = serialize_test_output(sierra, "RangeCheck", &[("RangeCheck", 1)], |_| {
+            vec![MaybeRelocatable::from(0)]
+        });
+
+        assert!(matches!(
+            result,
+            Err(SerializeOutputError::UnsupportedType(_))
+        ));
+    }
 }
@@ -0,0 +1,63 @@
+use serde::Serialize;
+use thiserror::Error;
+
+/// Errors raised while converting JSON values to Cairo arguments and Cairo outputs to JSON.
+///
+/// Each variant carries the JSON path (e.g. `$.orders[2].amount`) of the value being processed.
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IoSerdeError {
+    #[error("Invalid JSON at {path}: {message}")]
+    InvalidJson { path: String, message: String },
+    #[error("Missing field: {field} at {path}")]
+    MissingField { path: String, field: String },
+    #[error("Type mismatch at {path}: {message}")]
+    TypeMismatch { path: String, message: String },
+    #[error("Truncated output at {path}: unexpected end of output")]
+    TruncatedOutput { path: String },
+    #[error("Unknown schema at {path}: {name} not found in schema")]
+    UnknownSchema { path: String, name: String },
+}
+
+impl IoSerdeError {
+    /// Returns the JSON path of the value that caused the error.
+    pub fn path(&self) -> &str {
+        match self {
+            IoSerdeError::InvalidJson { path, .. }
+            | IoSerdeError::MissingField { path, .. }
+            | IoSerdeError::TypeMismatch { path, .. }
+            | IoSerdeError::TruncatedOutput { path }
+            | IoSerdeError::UnknownSchema { path, .. } => path,
+        }
+    }
+
+    pub(crate) fn type_mismatch(path: &str, message: impl Into<String>) -> Self {
+        IoSerdeError::TypeMismatch {
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn unknown_schema(path: &str, name: impl Into<String>) -> Self {
+        IoSerdeError::UnknownSchema {
+            path: path.to_string(),
+            name: name.into(),
+        }
+    }
+
+    pub(crate) fn truncated_output(path: &str) -> Self {
+        IoSerdeError::TruncatedOutput {
+            path: path.to_string(),
+        }
+    }
+}
+
+pub(crate) const ROOT_PATH: &str = "$";
+
+pub(crate) fn field_path(path: &str, field: &str) -> String {
+    format!("{}.{}", path, field)
+}
+
+pub(crate) fn index_path(path: &str, index: usize) -> String {
+    format!("{}[{}]", path, index)
+}
@@ -0,0 +1,319 @@
+use serde_json::{json, Map, Value};
+
+use crate::{
+    error::{field_path, index_path, IoSerdeError, ROOT_PATH},
+    schema::{CairoInput, NamedSchemaType, Schema, SchemaType},
+};
+
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+// Inputs and outputs of the same Cairo type are not always written the same way in JSON,
+// e.g. wide integers are accepted as numbers or strings but always returned as decimal strings.
+#[derive(Clone, Copy)]
+enum Direction {
+    Input,
+    Output,
+}
+
+/// Returns a JSON Schema (draft 2020-12) document describing the `--args` payload.
+pub fn input_json_schema(schema: &Schema) -> Result<Value, IoSerdeError> {
+    let mut builder = JsonSchemaBuilder::new(schema, Direction::Input);
+    let root = match &schema.cairo_input {
+        CairoInput::Schema(name) if name.is_empty() => json!({ "type": "object" }),
+        CairoInput::Schema(name) => builder.struct_ref(name, ROOT_PATH)?,
+        CairoInput::Params(params) => builder.object(params, ROOT_PATH)?,
+    };
+    Ok(builder.document("Agent input", root))
+}
+
+/// Returns a JSON Schema (draft 2020-12) document describing the output of a run.
+pub fn output_json_schema(schema: &Schema) -> Result<Value, IoSerdeError> {
+    let mut builder = JsonSchemaBuilder::new(schema, Direction::Output);
+    let root = if schema.has_output() {
+        builder.struct_ref(&schema.cairo_output, ROOT_PATH)?
+    } else {
+        // Without an output schema, the run prints the serialized return value as it is
+        json!({})
+    };
+    Ok(builder.document("Agent output", root))
+}
+
+struct JsonSchemaBuilder<'a> {
+    schema: &'a Schema,
+    direction: Direction,
+    defs: Map<String, Value>,
+}
+
+impl<'a> JsonSchemaBuilder<'a> {
+    fn new(schema: &'a Schema, direction: Direction) -> Self {
+        JsonSchemaBuilder {
+            schema,
+            direction,
+            defs: Map::new(),
+        }
+    }
+
+    fn document(self, title: &str, root: Value) -> Value {
+        let mut document = json!({
+            "$schema": JSON_SCHEMA_DIALECT,
+            "title": title,
+        });
+        if let (Value::Object(document), Value::Object(root)) = (&mut document, root) {
+            document.extend(root);
+        }
+        if !self.defs.is_empty() {
+            document["$defs"] = Value::Object(self.defs);
+        }
+        document
+    }
+
+    fn object(&mut self, fields: &[NamedSchemaType], path: &str) -> Result<Value, IoSerdeError> {
+        let mut properties = Map::new();
+        for field in fields {
+            let field_schema = self.value(&field.ty, &field_path(path, &field.name))?;
+            properties.insert(field.name.clone(), field_schema);
+        }
+        let required = fields
+            .iter()
+            .map(|field| field.name.clone())
+            .collect::<Vec<_>>();
+        Ok(json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        }))
+    }
+
+    fn struct_ref(&mut self, name: &str, path: &str) -> Result<Value, IoSerdeError> {
+        if !self.defs.contains_key(name) {
+            let schema_def =
+                self.schema.schemas.get(name).ok_or_else(|| {
+                    IoSerdeError::unknown_schema(path, format!("Schema {}", name))
+                })?;
+            // Register the name first so that recursive types terminate
+            self.defs.insert(name.to_string(), Value::Null);
+            let object = self.object(&schema_def.fields, path)?;
+            self.defs.insert(name.to_string(), object);
+        }
+        Ok(def_ref(name))
+    }
+
+    fn enum_ref(&mut self, name: &str, path: &str) -> Result<Value, IoSerdeError> {
+        if !self.defs.contains_key(name) {
+            let enum_def = self
+                .schema
+                .enums
+                .get(name)
+                .ok_or_else(|| IoSerdeError::unknown_schema(path, format!("Enum {}", name)))?;
+            self.defs.insert(name.to_string(), Value::Null);
+            // Unit variants are written as their name, others as a single-key object
+            let mut variants = Vec::new();
+            for variant in &enum_def.variants {
+                let variant_schema = match &variant.ty {
+                    None => json!({ "const": variant.name }),
+                    Some(ty) => {
+                        let payload = self.value(ty, &field_path(path, &variant.name))?;
+                        json!({
+                            "type": "object",
+                            "properties": { variant.name.clone(): payload },
+                            "required": [variant.name],
+                            "additionalProperties": false,
+                        })
+                    }
+                };
+                variants.push(variant_schema);
+            }
+            self.defs
+                .insert(name.to_string(), json!({ "oneOf": variants }));
+        }
+        Ok(def_ref(name))
+    }
+
+    fn value(&mut self, ty: &SchemaType, path: &str) -> Result<Value, IoSerdeError> {
+        match ty {
+            SchemaType::Primitive { name } => self.primitive(name, path),
+            SchemaType::Array { item_type } | SchemaType::Span { item_type } => Ok(json!({
+                "type": "array",
+                "items": self.value(item_type, &format!("{}[]", path))?,
+            })),
+            SchemaType::Struct { name } => self.struct_ref(name, path),
+            SchemaType::Enum { name } => self.enum_ref(name, path),
+            SchemaType::Option { item_type } => Ok(json!({
+                "anyOf": [self.value(item_type, path)?, { "type": "null" }],
+            })),
+            SchemaType::Tuple { items } => {
+                let prefix_items = items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| self.value(item, &index_path(path, i)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(json!({
+                    "type": "array",
+                    "prefixItems": prefix_items,
+                    "minItems": items.len(),
+                    "maxItems": items.len(),
+                }))
+            }
+            SchemaType::Tensor { item_type } => {
+                let item = self.value(item_type, &field_path(path, "data"))?;
+                let tensor = json!({
+                    "type": "object",
+                    "properties": {
+                        "shape": { "type": "array", "items": { "type": "integer", "minimum": 0 } },
+                        "data": { "type": "array", "items": item },
+                    },
+                    "required": ["shape", "data"],
+                });
+                match self.direction {
+                    // Input tensors may also be given as nested arrays
+                    Direction::Input => Ok(json!({ "anyOf": [tensor, { "type": "array" }] })),
+                    Direction::Output => Ok(tensor),
+                }
+            }
+        }
+    }
+
+    fn primitive(&self, name: &str, path: &str) -> Result<Value, IoSerdeError> {
+        let integer = |minimum: i128, maximum: u128| json!({ "type": "integer", "minimum": minimum, "maximum": maximum });
+        let value = match name {
+            "u8" => integer(0, u8::MAX.into()),
+            "u16" => integer(0, u16::MAX.into()),
+            "u32" => integer(0, u32::MAX.into()),
+            "u64" => integer(0, u64::MAX.into()),
+            "i8" => integer(i8::MIN.into(), i8::MAX as u128),
+            "i16" => integer(i16::MIN.into(), i16::MAX as u128),
+            "i32" => integer(i32::MIN.into(), i32::MAX as u128),
+            "i64" => integer(i64::MIN.into(), i64::MAX as u128),
+            "u128" | "i128" | "u256" => match self.direction {
+                Direction::Input => json!({
+                    "anyOf": [
+                        { "type": "integer" },
+                        { "type": "string", "pattern": "^(-?[0-9]+|0x[0-9a-fA-F]+)$" },
+                    ],
+                }),
+                Direction::Output => json!({ "type": "string", "pattern": "^-?[0-9]+$" }),
+            },
+            "F64" => json!({ "type": "number" }),
+            "felt252" => match self.direction {
+                // A decimal or hex number, or a short string
+                Direction::Input => json!({ "type": "string" }),
+                Direction::Output => json!({ "type": "string", "pattern": "^0x[0-9a-f]+$" }),
+            },
+            "ByteArray" => json!({ "type": "string" }),
+            "bool" => json!({ "type": "boolean" }),
+            _ => {
+                return Err(IoSerdeError::unknown_schema(
+                    path,
+                    format!("Primitive type {}", name),
+                ))
+            }
+        };
+        Ok(value)
+    }
+}
+
+fn def_ref(name: &str) -> Value {
+    // Escape the name as a JSON pointer token
+    let token = name.replace('~', "~0").replace('/', "~1");
+    json!({ "$ref": format!("#/$defs/{}", token) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::parse_schema_file;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn create_temp_file_with_content(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema_content = r#"
+        schemas:
+            Point:
+                fields:
+                    - x:
+                        type: Primitive
+                        name: u32
+                    - y:
+                        type: Primitive
+                        name: u128
+        enums:
+            Action:
+                variants:
+                    - Idle
+                    - Move:
+                        type: Struct
+                        name: Point
+        cairo_input:
+            - points:
+                type: Array
+                item_type:
+                    type: Struct
+                    name: Point
+            - action:
+                type: Option
+                item_type:
+                    type: Enum
+                    name: Action
+        cairo_output: Point
+        "#;
+
+        let schema_file = create_temp_file_with_content(schema_content);
+        let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        let input = input_json_schema(&schema).unwrap();
+        assert_eq!(input["$schema"], JSON_SCHEMA_DIALECT);
+        assert_eq!(input["type"], "object");
+        assert_eq!(input["required"], json!(["points", "action"]));
+        assert_eq!(
+            input["properties"]["points"],
+            json!({ "type": "array", "items": { "$ref": "#/$defs/Point" } })
+        );
+        assert_eq!(
+            input["properties"]["action"]["anyOf"][0],
+            json!({ "$ref": "#/$defs/Action" })
+        );
+        assert_eq!(
+            input["$defs"]["Action"]["oneOf"][0],
+            json!({ "const": "Idle" })
+        );
+        assert_eq!(
+            input["$defs"]["Action"]["oneOf"][1]["properties"]["Move"],
+            json!({ "$ref": "#/$defs/Point" })
+        );
+        assert!(input["$defs"]["Point"]["properties"]["y"]["anyOf"].is_array());
+
+        let output = output_json_schema(&schema).unwrap();
+        assert_eq!(output["$ref"], "#/$defs/Point");
+        assert_eq!(
+            output["$defs"]["Point"]["properties"]["y"],
+            json!({ "type": "string", "pattern": "^-?[0-9]+$" })
+        );
+    }
+
+    #[test]
+    fn test_json_schema_unknown_schema() {
+        let schema_content = r#"
+        schemas: {}
+        cairo_input: Missing
+        cairo_output: Missing
+        "#;
+
+        let schema_file = create_temp_file_with_content(schema_content);
+        let schema = parse_schema_file(&schema_file.path().to_path_buf()).unwrap();
+
+        assert_eq!(
+            output_json_schema(&schema).unwrap_err(),
+            IoSerdeError::UnknownSchema {
+                path: "$".to_string(),
+                name: "Schema Missing".to_string()
+            }
+        );
+    }
+}
@@ -2,9 +2,14 @@ use cairo_vm::Felt252;
 
 pub mod cairo_input;
 pub mod cairo_output;
+pub mod error;
+pub mod json_schema;
 pub mod schema;
+pub mod schema_inference;
 pub(crate) mod utils;
 
+pub use error::IoSerdeError;
+
 #[allow(dead_code)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum FuncArg {
@@ -13,6 +13,10 @@ pub(crate) enum SchemaType {
     Array { item_type: Box<SchemaType> },
     Span { item_type: Box<SchemaType> },
     Struct { name: String },
+    Tensor { item_type: Box<SchemaType> },
+    Enum { name: String },
+    Option { item_type: Box<SchemaType> },
+    Tuple { items: Vec<SchemaType> },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,13 +66,222 @@ impl<'de> Visitor<'de> for NamedSchemaTypeVisitor {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct EnumDef {
+    pub(crate) variants: Vec<NamedVariant>,
+}
+
+/// An enum variant, written either as a bare name (no payload) or as a single-key map
+/// from the variant name to the payload type.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct NamedVariant {
+    pub(crate) name: String,
+    pub(crate) ty: Option<SchemaType>,
+}
+
+impl<'de> Deserialize<'de> for NamedVariant {
+    fn deserialize<D>(deserializer: D) -> Result<NamedVariant, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(NamedVariantVisitor)
+    }
+}
+
+struct NamedVariantVisitor;
+
+impl<'de> Visitor<'de> for NamedVariantVisitor {
+    type Value = NamedVariant;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a variant name or a map with a single key-value pair")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<NamedVariant, E>
+    where
+        E: de::Error,
+    {
+        Ok(NamedVariant {
+            name: value.to_string(),
+            ty: None,
+        })
+    }
+
+    fn visit_map<M>(self, mut map: M) -> Result<NamedVariant, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        if let Some((key, value)) = map.next_entry::<String, Option<SchemaType>>()? {
+            if map.next_key::<de::IgnoredAny>()?.is_some() {
+                return Err(de::Error::custom("Expected only one key per variant"));
+            }
+            Ok(NamedVariant {
+                name: key,
+                ty: value,
+            })
+        } else {
+            Err(de::Error::custom("Expected at least one key-value pair"))
+        }
+    }
+}
+
+/// The input of the entry function, either the name of a schema serialized into a single
+/// `Array<felt252>` argument, or the list of the function parameters in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged, from = "RawCairoInput")]
+pub(crate) enum CairoInput {
+    Schema(String),
+    Params(Vec<NamedSchemaType>),
+}
+
+// Accepts a missing (`null`) input schema
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawCairoInput {
+    Schema(Option<String>),
+    Params(Vec<NamedSchemaType>),
+}
+
+impl From<RawCairoInput> for CairoInput {
+    fn from(raw: RawCairoInput) -> Self {
+        match raw {
+            RawCairoInput::Schema(name) => CairoInput::Schema(name.unwrap_or_default()),
+            RawCairoInput::Params(params) => CairoInput::Params(params),
+        }
+    }
+}
+
+impl Default for CairoInput {
+    fn default() -> Self {
+        CairoInput::Schema(String::new())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Schema {
     pub(crate) schemas: HashMap<String, SchemaDef>,
-    pub(crate) cairo_input: String,
+    #[serde(default)]
+    pub(crate) enums: HashMap<String, EnumDef>,
+    #[serde(default)]
+    pub(crate) cairo_input: CairoInput,
+    #[serde(default)]
+    pub(crate) cairo_output: String,
+    /// The input and output of other entry functions, by function path
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) functions: HashMap<String, FunctionSchema>,
+}
+
+/// The input and output of an entry function, using the types defined by the [`Schema`].
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub(crate) struct FunctionSchema {
+    #[serde(default)]
+    pub(crate) cairo_input: CairoInput,
+    #[serde(default)]
     pub(crate) cairo_output: String,
 }
 
+impl Schema {
+    /// Uses the input and output of the function at `function_path` (e.g. `strategies::run`),
+    /// if the schema has a section for it. Returns whether it has one.
+    pub fn select_function(&mut self, function_path: &str) -> bool {
+        let function_path = function_path.trim_start_matches("::");
+        match self.functions.get(function_path) {
+            Some(function) => {
+                self.cairo_input = function.cairo_input.clone();
+                self.cairo_output = function.cairo_output.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the name and memory size (in felts) of each input argument, or `None` if the schema
+    /// doesn't define an input.
+    pub fn input_layout(&self) -> Result<Option<Vec<(String, i16)>>, String> {
+        match &self.cairo_input {
+            CairoInput::Schema(name) if name.is_empty() => Ok(None),
+            // The whole input is passed as a single `Array<felt252>`
+            CairoInput::Schema(name) => Ok(Some(vec![(name.clone(), 2)])),
+            CairoInput::Params(params) => params
+                .iter()
+                .map(|param| Ok((param.name.clone(), self.type_size(&param.ty)?)))
+                .collect::<Result<Vec<_>, String>>()
+                .map(Some),
+        }
+    }
+
+    /// Returns whether the schema defines an output.
+    pub fn has_output(&self) -> bool {
+        !self.cairo_output.is_empty()
+    }
+
+    /// Returns the name and memory size (in felts) of each output field, or `None` if the schema
+    /// doesn't define an output.
+    pub fn output_layout(&self) -> Result<Option<Vec<(String, i16)>>, String> {
+        self.layout(&self.cairo_output)
+    }
+
+    fn layout(&self, schema_name: &str) -> Result<Option<Vec<(String, i16)>>, String> {
+        if schema_name.is_empty() {
+            return Ok(None);
+        }
+        let schema_def = self
+            .schemas
+            .get(schema_name)
+            .ok_or_else(|| format!("Schema {} not found", schema_name))?;
+        schema_def
+            .fields
+            .iter()
+            .map(|field| Ok((field.name.clone(), self.type_size(&field.ty)?)))
+            .collect::<Result<Vec<_>, String>>()
+            .map(Some)
+    }
+
+    // Returns the number of felts a value of this type takes in the VM memory,
+    // matching the sizes computed by `cairo_lang_sierra_type_size`
+    pub(crate) fn type_size(&self, ty: &SchemaType) -> Result<i16, String> {
+        match ty {
+            SchemaType::Primitive { name } => match name.as_str() {
+                "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128"
+                | "felt252" | "bool" | "F64" => Ok(1),
+                "u256" => Ok(2),
+                // data (Array<bytes31>), pending_word and pending_word_len
+                "ByteArray" => Ok(4),
+                _ => Err(format!("Unknown primitive type: {}", name)),
+            },
+            SchemaType::Array { .. } | SchemaType::Span { .. } => Ok(2),
+            // shape and data spans
+            SchemaType::Tensor { .. } => Ok(4),
+            SchemaType::Struct { name } => {
+                let schema_def = self
+                    .schemas
+                    .get(name)
+                    .ok_or_else(|| format!("Schema {} not found", name))?;
+                schema_def
+                    .fields
+                    .iter()
+                    .map(|field| self.type_size(&field.ty))
+                    .sum()
+            }
+            SchemaType::Tuple { items } => items.iter().map(|item| self.type_size(item)).sum(),
+            SchemaType::Option { item_type } => Ok(1 + self.type_size(item_type)?),
+            SchemaType::Enum { name } => {
+                let enum_def = self
+                    .enums
+                    .get(name)
+                    .ok_or_else(|| format!("Enum {} not found in schema", name))?;
+                let mut max_payload_size = 0;
+                for variant in &enum_def.variants {
+                    if let Some(ty) = &variant.ty {
+                        max_payload_size = max_payload_size.max(self.type_size(ty)?);
+                    }
+                }
+                Ok(1 + max_payload_size)
+            }
+        }
+    }
+}
+
 pub fn parse_schema_file(path: &PathBuf) -> Result<Schema, String> {
     let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
     let mut contents = String::new();
@@ -77,3 +290,56 @@ pub fn parse_schema_file(path: &PathBuf) -> Result<Schema, String> {
 
     serde_yaml::from_str(&contents).map_err(|e| format!("Failed to parse YAML: {}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_select_function() {
+        let schema_content = r#"
+        schemas:
+            Input:
+                fields:
+                    - n:
+                        type: Primitive
+                        name: u32
+            Output:
+                fields:
+                    - result:
+                        type: Primitive
+                        name: u32
+        cairo_input: Input
+        cairo_output: Output
+        functions:
+            strategies::momentum::run:
+                cairo_input:
+                    - window:
+                        type: Primitive
+                        name: u32
+                cairo_output: Output
+        "#;
+
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(schema_content.as_bytes()).unwrap();
+        let mut schema = parse_schema_file(&file.path().to_path_buf()).unwrap();
+
+        assert!(!schema.select_function("strategies::mean_reversion::run"));
+        assert_eq!(
+            schema.input_layout().unwrap(),
+            Some(vec![("Input".to_string(), 2)])
+        );
+
+        assert!(schema.select_function("::strategies::momentum::run"));
+        assert_eq!(
+            schema.input_layout().unwrap(),
+            Some(vec![("window".to_string(), 1)])
+        );
+        assert_eq!(
+            schema.output_layout().unwrap(),
+            Some(vec![("result".to_string(), 1)])
+        );
+    }
+}
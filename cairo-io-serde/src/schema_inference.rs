@@ -0,0 +1,391 @@
+use cairo_lang_sierra::{
+    extensions::{
+        core::{CoreLibfunc, CoreType, CoreTypeConcrete},
+        types::TypeInfo,
+    },
+    ids::ConcreteTypeId,
+    program::{GenericArg, Program},
+    program_registry::ProgramRegistry,
+};
+
+use crate::schema::{
+    CairoInput, EnumDef, NamedSchemaType, NamedVariant, Schema, SchemaDef, SchemaType,
+};
+
+/// Name of the schema wrapping an inferred return value that is not a struct.
+pub const INFERRED_OUTPUT_SCHEMA: &str = "Output";
+
+/// Builds a [`Schema`] from the signature of the function ending with `entry_func_name`.
+///
+/// Implicit arguments (builtins, gas, system) are skipped and a `PanicResult` return value is
+/// unwrapped. Sierra keeps no struct member or enum variant names, so parameters are named after
+/// their debug name when available and members and variants after their position
+/// (`field_0`, `variant_0`, ...). A `cairo_schema.yaml` can be used to override them.
+///
+/// A function returning an `Array<felt252>` gets no output schema, its return value being the
+/// serialized output already.
+pub fn infer_schema(sierra_program: &Program, entry_func_name: &str) -> Result<Schema, String> {
+    let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(sierra_program)
+        .map_err(|e| format!("Failed to build Sierra program registry: {}", e))?;
+    let func = sierra_program
+        .funcs
+        .iter()
+        .find(|f| {
+            f.id.debug_name
+                .as_ref()
+                .is_some_and(|name| name.ends_with(entry_func_name))
+        })
+        .ok_or_else(|| format!("Function {} not found in Sierra program", entry_func_name))?;
+
+    let mut inferrer = SchemaInferrer {
+        registry: &registry,
+        schema: Schema::default(),
+    };
+
+    let mut input_fields = Vec::new();
+    for (i, param) in func.params.iter().enumerate() {
+        if inferrer.is_implicit(&param.ty)? {
+            continue;
+        }
+        let name = param
+            .id
+            .debug_name
+            .as_ref()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("arg_{}", i));
+        let ty = inferrer.infer_type(&param.ty)?;
+        input_fields.push(NamedSchemaType { name, ty });
+    }
+    inferrer.schema.cairo_input = CairoInput::Params(input_fields);
+
+    let return_type = match func.signature.ret_types.last() {
+        Some(ty) if !inferrer.is_implicit(ty)? => Some(inferrer.unwrap_panic_result(ty)?),
+        _ => None,
+    };
+    // A returned `Array<felt252>` already holds the serialized output, which is printed as it is
+    // without an output schema
+    if let Some(ty) = &return_type {
+        if inferrer.is_felt252_array(ty)? {
+            return Ok(inferrer.schema);
+        }
+    }
+    let output_fields = match return_type {
+        Some(ty) => match inferrer.infer_type(&ty)? {
+            SchemaType::Struct { name } => {
+                inferrer.schema.cairo_output = name;
+                return Ok(inferrer.schema);
+            }
+            SchemaType::Tuple { items } => items
+                .into_iter()
+                .enumerate()
+                .map(|(i, ty)| NamedSchemaType {
+                    name: format!("field_{}", i),
+                    ty,
+                })
+                .collect(),
+            ty => vec![NamedSchemaType {
+                name: "value".to_string(),
+                ty,
+            }],
+        },
+        None => Vec::new(),
+    };
+    inferrer.schema.schemas.insert(
+        INFERRED_OUTPUT_SCHEMA.to_string(),
+        SchemaDef {
+            fields: output_fields,
+        },
+    );
+    inferrer.schema.cairo_output = INFERRED_OUTPUT_SCHEMA.to_string();
+
+    Ok(inferrer.schema)
+}
+
+struct SchemaInferrer<'a> {
+    registry: &'a ProgramRegistry<CoreType, CoreLibfunc>,
+    schema: Schema,
+}
+
+impl SchemaInferrer<'_> {
+    fn get_type(&self, ty: &ConcreteTypeId) -> Result<&CoreTypeConcrete, String> {
+        self.registry
+            .get_type(ty)
+            .map_err(|e| format!("Failed to get type {}: {}", ty, e))
+    }
+
+    // Returns true if the type is an implicit argument (aka a builtin, gas, or system type)
+    fn is_implicit(&self, ty: &ConcreteTypeId) -> Result<bool, String> {
+        Ok(matches!(
+            self.get_type(ty)?,
+            CoreTypeConcrete::RangeCheck(_)
+                | CoreTypeConcrete::RangeCheck96(_)
+                | CoreTypeConcrete::GasBuiltin(_)
+                | CoreTypeConcrete::BuiltinCosts(_)
+                | CoreTypeConcrete::Bitwise(_)
+                | CoreTypeConcrete::EcOp(_)
+                | CoreTypeConcrete::Pedersen(_)
+                | CoreTypeConcrete::Poseidon(_)
+                | CoreTypeConcrete::SegmentArena(_)
+                | CoreTypeConcrete::StarkNet(_)
+                | CoreTypeConcrete::Circuit(_)
+        ))
+    }
+
+    fn is_felt252_array(&self, ty: &ConcreteTypeId) -> Result<bool, String> {
+        match self.get_type(ty)? {
+            CoreTypeConcrete::Array(info) => Ok(matches!(
+                self.get_type(&info.ty)?,
+                CoreTypeConcrete::Felt252(_)
+            )),
+            _ => Ok(false),
+        }
+    }
+
+    // Returns the T type in PanicResult::Ok((T,)), or the type itself if it is not a PanicResult
+    fn unwrap_panic_result(&self, ty: &ConcreteTypeId) -> Result<ConcreteTypeId, String> {
+        if let CoreTypeConcrete::Enum(info) = self.get_type(ty)? {
+            if user_type_name(&info.info)
+                .is_some_and(|name| name.starts_with("core::panics::PanicResult"))
+            {
+                if let CoreTypeConcrete::Struct(inner) = self.get_type(&info.variants[0])? {
+                    if let [inner_ty] = inner.members.as_slice() {
+                        return Ok(inner_ty.clone());
+                    }
+                }
+            }
+        }
+        Ok(ty.clone())
+    }
+
+    fn infer_type(&mut self, ty: &ConcreteTypeId) -> Result<SchemaType, String> {
+        let primitive = |name: &str| {
+            Ok(SchemaType::Primitive {
+                name: name.to_string(),
+            })
+        };
+
+        match self.get_type(ty)? {
+            CoreTypeConcrete::Felt252(_) | CoreTypeConcrete::Bytes31(_) => primitive("felt252"),
+            CoreTypeConcrete::Uint8(_) => primitive("u8"),
+            CoreTypeConcrete::Uint16(_) => primitive("u16"),
+            CoreTypeConcrete::Uint32(_) => primitive("u32"),
+            CoreTypeConcrete::Uint64(_) => primitive("u64"),
+            CoreTypeConcrete::Uint128(_) => primitive("u128"),
+            CoreTypeConcrete::Sint8(_) => primitive("i8"),
+            CoreTypeConcrete::Sint16(_) => primitive("i16"),
+            CoreTypeConcrete::Sint32(_) => primitive("i32"),
+            CoreTypeConcrete::Sint64(_) => primitive("i64"),
+            CoreTypeConcrete::Sint128(_) => primitive("i128"),
+            CoreTypeConcrete::Array(info) => {
+                let item = info.ty.clone();
+                let item_type = self.infer_type(&item)?;
+                Ok(SchemaType::Array {
+                    item_type: Box::new(item_type),
+                })
+            }
+            // These wrappers are serialized as their inner value
+            CoreTypeConcrete::Box(info)
+            | CoreTypeConcrete::NonZero(info)
+            | CoreTypeConcrete::Snapshot(info) => {
+                let inner = info.ty.clone();
+                self.infer_type(&inner)
+            }
+            CoreTypeConcrete::Struct(info) => {
+                let name = user_type_name(&info.info).unwrap_or_else(|| ty.to_string());
+                let members = info.members.clone();
+                self.infer_struct(name, &members)
+            }
+            CoreTypeConcrete::Enum(info) => {
+                let name = user_type_name(&info.info).unwrap_or_else(|| ty.to_string());
+                let variants = info.variants.clone();
+                self.infer_enum(name, &variants)
+            }
+            _ => Err(format!("Cannot infer schema for type {}", ty)),
+        }
+    }
+
+    fn infer_struct(
+        &mut self,
+        name: String,
+        members: &[ConcreteTypeId],
+    ) -> Result<SchemaType, String> {
+        match name.as_str() {
+            "core::byte_array::ByteArray" => {
+                return Ok(SchemaType::Primitive {
+                    name: "ByteArray".to_string(),
+                })
+            }
+            "core::integer::u256" => {
+                return Ok(SchemaType::Primitive {
+                    name: "u256".to_string(),
+                })
+            }
+            "Tuple" => {
+                let items = members
+                    .iter()
+                    .map(|member| self.infer_type(member))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(SchemaType::Tuple { items });
+            }
+            _ => {}
+        }
+
+        let base_name = name.split("::<").next().unwrap_or(&name);
+        if base_name == "core::array::Span" {
+            // Span<T> holds a single @Array<T> member
+            if let [member] = members {
+                if let SchemaType::Array { item_type } = self.infer_type(member)? {
+                    return Ok(SchemaType::Span { item_type });
+                }
+            }
+        } else if base_name.ends_with("::F64") {
+            // Orion fixed point numbers
+            return Ok(SchemaType::Primitive {
+                name: "F64".to_string(),
+            });
+        } else if base_name.ends_with("::Tensor") {
+            // Orion tensors hold a shape span and a data span
+            if let [_, data] = members {
+                if let SchemaType::Span { item_type } = self.infer_type(data)? {
+                    return Ok(SchemaType::Tensor { item_type });
+                }
+            }
+        }
+
+        if !self.schema.schemas.contains_key(&name) {
+            // Register the name first so that recursive types terminate
+            self.schema
+                .schemas
+                .insert(name.clone(), SchemaDef { fields: Vec::new() });
+            let fields = members
+                .iter()
+                .enumerate()
+                .map(|(i, member)| {
+                    Ok(NamedSchemaType {
+                        name: format!("field_{}", i),
+                        ty: self.infer_type(member)?,
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            self.schema
+                .schemas
+                .insert(name.clone(), SchemaDef { fields });
+        }
+        Ok(SchemaType::Struct { name })
+    }
+
+    fn infer_enum(
+        &mut self,
+        name: String,
+        variants: &[ConcreteTypeId],
+    ) -> Result<SchemaType, String> {
+        let base_name = name.split("::<").next().unwrap_or(&name);
+        if base_name == "core::bool" {
+            return Ok(SchemaType::Primitive {
+                name: "bool".to_string(),
+            });
+        }
+        if base_name == "core::option::Option" {
+            let item_type = self.infer_type(&variants[0])?;
+            return Ok(SchemaType::Option {
+                item_type: Box::new(item_type),
+            });
+        }
+
+        if !self.schema.enums.contains_key(&name) {
+            self.schema.enums.insert(
+                name.clone(),
+                EnumDef {
+                    variants: Vec::new(),
+                },
+            );
+            let mut named_variants = Vec::new();
+            for (i, variant) in variants.iter().enumerate() {
+                let ty = if self.is_unit(variant)? {
+                    None
+                } else {
+                    Some(self.infer_type(variant)?)
+                };
+                named_variants.push(NamedVariant {
+                    name: format!("variant_{}", i),
+                    ty,
+                });
+            }
+            self.schema.enums.insert(
+                name.clone(),
+                EnumDef {
+                    variants: named_variants,
+                },
+            );
+        }
+        Ok(SchemaType::Enum { name })
+    }
+
+    fn is_unit(&self, ty: &ConcreteTypeId) -> Result<bool, String> {
+        Ok(matches!(
+            self.get_type(ty)?,
+            CoreTypeConcrete::Struct(info) if info.members.is_empty()
+        ))
+    }
+}
+
+fn user_type_name(info: &TypeInfo) -> Option<String> {
+    match info.long_id.generic_args.first() {
+        Some(GenericArg::UserType(user_type)) => {
+            user_type.debug_name.as_ref().map(|name| name.to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cairo_lang_sierra::ProgramParser;
+
+    #[test]
+    fn test_infer_schema() {
+        let program = ProgramParser::new()
+            .parse(
+                r#"
+                type RangeCheck = RangeCheck;
+                type felt252 = felt252;
+                type u32 = u32;
+                type Array<felt252> = Array<felt252>;
+                type Unit = Struct<ut@Tuple>;
+                type core::bool = Enum<ut@core::bool, Unit, Unit>;
+                type agent::Point = Struct<ut@agent::Point, u32, felt252>;
+                type Tuple<agent::Point, core::bool> = Struct<ut@Tuple, agent::Point, core::bool>;
+
+                agent::main@0(rc: RangeCheck, n: u32, data: Array<felt252>) -> (RangeCheck, Tuple<agent::Point, core::bool>);
+                "#,
+            )
+            .unwrap();
+
+        let schema = infer_schema(&program, "::main").unwrap();
+
+        let CairoInput::Params(params) = &schema.cairo_input else {
+            panic!("Expected the inferred input to be a list of params");
+        };
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].name, "n");
+        assert!(matches!(&params[0].ty, SchemaType::Primitive { name } if name == "u32"));
+        assert_eq!(params[1].name, "data");
+        assert!(matches!(
+            &params[1].ty,
+            SchemaType::Array { item_type } if matches!(**item_type, SchemaType::Primitive { ref name } if name == "felt252")
+        ));
+
+        let output = &schema.schemas[&schema.cairo_output];
+        assert_eq!(output.fields.len(), 2);
+        assert!(
+            matches!(&output.fields[0].ty, SchemaType::Struct { name } if name == "agent::Point")
+        );
+        assert!(matches!(&output.fields[1].ty, SchemaType::Primitive { name } if name == "bool"));
+
+        let point = &schema.schemas["agent::Point"];
+        assert_eq!(point.fields[0].name, "field_0");
+        assert!(matches!(&point.fields[0].ty, SchemaType::Primitive { name } if name == "u32"));
+        assert!(matches!(&point.fields[1].ty, SchemaType::Primitive { name } if name == "felt252"));
+    }
+}
@@ -16,7 +16,7 @@ use cairo_lang_test_plugin::{
     compile_test_prepared_db, test_plugin_suite, TestCompilation, TestCompilationMetadata,
     TestConfig, TestsCompilationConfig,
 };
-use cairo_oracle_hint_processor::{run_1, Error};
+use cairo_oracle_hint_processor::{run_1, Error, RunOptions};
 use cairo_proto_serde::configuration::Configuration;
 use cairo_vm::types::layout_name::LayoutName;
 use cairo_vm::Felt252 as VMFelt;
@@ -318,18 +318,24 @@ pub fn run_tests(
 
                 let r = run_1(
                     configuration,
-                    layout,
-                    &None,
-                    &None,
-                    &None,
-                    &None,
-                    &None,
-                    &FuncArgs::default(),
-                    &Schema::default(),
                     &sierra_program,
-                    &name,
-                    false,
-                );
+                    &Schema::default(),
+                    &FuncArgs::default(),
+                    &RunOptions::new()
+                        .layout(*layout)
+                        .entry_function(&name)
+                        .initial_gas(test.available_gas),
+                )
+                .and_then(|run_output| match run_output.panic_data {
+                    Some(panic_data) => Err(Error::RunPanic(panic_data)),
+                    None => Ok(run_output),
+                });
+
+                let gas_usage = r
+                    .as_ref()
+                    .ok()
+                    .and_then(|run_output| run_output.gas_consumed)
+                    .map(|gas_consumed| gas_consumed as i64);
 
                 Ok((
                     name,
@@ -356,7 +362,7 @@ pub fn run_tests(
                             },
                             Err(e) => panic!("Error: {:?}", e),
                         },
-                        gas_usage: None,
+                        gas_usage,
                     }),
                 ))
             },
@@ -0,0 +1,147 @@
+use cairo_io_serde::{schema::Schema, FuncArgs};
+use cairo_lang_sierra::program::Program as SierraProgram;
+use cairo_proto_serde::configuration::Configuration;
+
+use crate::{
+    cairo_run::{Cairo1RunConfig, PreparedProgram},
+    run::{RunOptions, RunOutput},
+    Error,
+};
+
+/// Runs the entry function of an agent many times, compiling the program only once.
+///
+/// Compiling Sierra to CASM, computing the type sizes and building the entry code are done by
+/// [`AgentRunner::new`], so each [`AgentRunner::run`] only executes the VM.
+pub struct AgentRunner {
+    prepared_program: PreparedProgram,
+    configuration: Configuration,
+    schema: Schema,
+    options: RunOptions,
+}
+
+impl AgentRunner {
+    /// Prepares the entry function of `options` in `sierra_program`
+    /// and validates `schema` against its signature.
+    /// The compiled program is loaded from the CASM cache directory of `options` when an earlier
+    /// run stored it there.
+    ///
+    /// Runs are in execution mode and use the entry function, layout, gas and limits of
+    /// `options`. Its proof mode and artifact options are not used.
+    pub fn new(
+        sierra_program: &SierraProgram,
+        configuration: Configuration,
+        schema: Schema,
+        options: &RunOptions,
+    ) -> Result<Self, Error> {
+        let cairo_run_config = Cairo1RunConfig {
+            layout: options.layout,
+            casm_cache_dir: options.casm_cache_dir.as_deref(),
+            ..Default::default()
+        };
+        let prepared_program = PreparedProgram::new(
+            sierra_program,
+            &options.entry_func_name,
+            &schema,
+            &cairo_run_config,
+        )?;
+
+        Ok(Self {
+            prepared_program,
+            configuration,
+            schema,
+            options: options.clone(),
+        })
+    }
+
+    /// Runs the entry function with `args`.
+    pub fn run(&self, args: &FuncArgs) -> Result<RunOutput, Error> {
+        let cairo_run_config = Cairo1RunConfig {
+            args: &args.0,
+            serialize_output: true,
+            layout: self.options.layout,
+            initial_gas: self.options.initial_gas,
+            max_steps: self.options.max_steps,
+            timeout: self.options.timeout,
+            ..Default::default()
+        };
+        let (_runner, run_output) =
+            self.prepared_program
+                .run(cairo_run_config, &self.configuration, &self.schema)?;
+
+        Ok(run_output)
+    }
+
+    /// The I/O schema used to read the arguments and write the output.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cairo_io_serde::FuncArg;
+    use cairo_lang_sierra::ProgramParser;
+    use cairo_vm::Felt252;
+    use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+    use serde_json::json;
+
+    use super::*;
+
+    const SIERRA: &str = r#"
+        type felt252 = felt252;
+
+        libfunc felt252_add = felt252_add;
+        libfunc store_temp<felt252> = store_temp<felt252>;
+
+        felt252_add([0], [1]) -> ([2]);
+        store_temp<felt252>([2]) -> ([2]);
+        return([2]);
+
+        test::main@0([0]: felt252, [1]: felt252) -> (felt252);
+    "#;
+
+    fn schema() -> Schema {
+        serde_json::from_value(json!({
+            "schemas": {
+                "Output": {
+                    "fields": [{"sum": {"type": "Primitive", "name": "felt252"}}],
+                },
+            },
+            "cairo_input": [
+                {"a": {"type": "Primitive", "name": "felt252"}},
+                {"b": {"type": "Primitive", "name": "felt252"}},
+            ],
+            "cairo_output": "Output",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_agent_runner_batch() {
+        let sierra_program = ProgramParser::new().parse(SIERRA).unwrap();
+        let runner = AgentRunner::new(
+            &sierra_program,
+            Configuration::default(),
+            schema(),
+            &RunOptions::new(),
+        )
+        .unwrap();
+
+        // The runner is shared by the runs of a batch, which run in parallel
+        let outputs = (0..8u64)
+            .into_par_iter()
+            .map(|i| {
+                let args = FuncArgs(vec![
+                    FuncArg::Single(Felt252::from(i)),
+                    FuncArg::Single(Felt252::from(10)),
+                ]);
+                runner.run(&args).unwrap().output.unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        for (i, output) in outputs.iter().enumerate() {
+            let output: serde_json::Value = serde_json::from_str(output).unwrap();
+            assert_eq!(output, json!({"sum": format!("{:#x}", i + 10)}));
+        }
+    }
+}
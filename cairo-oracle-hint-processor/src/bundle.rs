@@ -0,0 +1,271 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
+
+use bincode::{enc::write::Writer, error::EncodeError};
+use cairo_lang_sierra::program::Program as SierraProgram;
+use cairo_vm::air_public_input::PublicInputError;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    prover_config::{
+        cpu_air_params, CpuAirProverConfig, CPU_AIR_PARAMS_FILE, CPU_AIR_PROVER_CONFIG_FILE,
+    },
+    run::{ResourcesReport, RunOutput},
+    Error,
+};
+
+/// The file of a prove bundle describing its other files.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+const TRACE_FILE: &str = "trace.bin";
+const MEMORY_FILE: &str = "memory.bin";
+
+/// The manifest of a prove bundle.
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleManifest {
+    /// The version of the runner which made the bundle
+    pub version: String,
+    /// The SHA-256 of the Sierra program, serialized as JSON
+    pub sierra_sha256: String,
+    pub entry_function: String,
+    pub layout: String,
+    pub resources: ResourcesReport,
+    /// The files of the bundle, by name
+    pub files: BTreeMap<String, BundleFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleFile {
+    pub sha256: String,
+    pub size: usize,
+}
+
+// Collects an encoded trace or memory, so that it can be hashed before being archived
+#[derive(Default)]
+struct BufferWriter(Vec<u8>);
+
+impl Writer for BufferWriter {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        self.0.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn to_json(value: &impl Serialize) -> Result<Vec<u8>, Error> {
+    Ok(serde_json::to_vec_pretty(value).map_err(io::Error::from)?)
+}
+
+/// Writes everything needed to prove a proof mode run to a zip archive at `path`.
+///
+/// The archive holds the encoded trace and memory, the AIR public and private inputs, the prover
+/// parameters, the oracle calls of the run, its JSON `input` and its decoded output. The AIR
+/// private input refers to the trace and memory files by their name in the archive, so the
+/// archive can be extracted anywhere.
+/// The [`BundleManifest`] with the hash of every file is written to [`MANIFEST_FILE`].
+///
+/// `run_output` must be the output of a run keeping the trace, memory and AIR inputs.
+pub fn write_prove_bundle(
+    path: &Path,
+    sierra_program: &SierraProgram,
+    entry_function: &str,
+    layout: &str,
+    input: &Value,
+    run_output: &RunOutput,
+) -> Result<BundleManifest, Error> {
+    if let Some(panic_data) = &run_output.panic_data {
+        return Err(Error::RunPanic(panic_data.clone()));
+    }
+    let artifacts = &run_output.artifacts;
+    let missing_artifact =
+        |artifact: &str| Error::ConfigError(format!("The prove bundle requires the {}", artifact));
+
+    let trace = artifacts
+        .trace
+        .as_ref()
+        .ok_or_else(|| missing_artifact("relocated trace"))?;
+    let mut trace_writer = BufferWriter::default();
+    cairo_vm::cairo_run::write_encoded_trace(trace, &mut trace_writer)?;
+
+    let memory = artifacts
+        .memory
+        .as_ref()
+        .ok_or_else(|| missing_artifact("relocated memory"))?;
+    let mut memory_writer = BufferWriter::default();
+    cairo_vm::cairo_run::write_encoded_memory(memory, &mut memory_writer)?;
+
+    let air_public_input = artifacts
+        .air_public_input
+        .clone()
+        .ok_or_else(|| missing_artifact("AIR public input"))?;
+    let air_private_input = artifacts
+        .air_private_input
+        .clone()
+        .ok_or_else(|| missing_artifact("AIR private input"))?
+        .to_serializable(TRACE_FILE.to_string(), MEMORY_FILE.to_string())
+        .serialize_json()
+        .map_err(PublicInputError::Serde)?;
+
+    // A schema-less output is kept as the string printed by the run
+    let output = run_output.output.clone().unwrap_or_default();
+    let output = serde_json::from_str::<Value>(&output).unwrap_or(Value::String(output));
+    let cpu_air_params = cpu_air_params(&air_public_input)?;
+
+    let files: Vec<(&str, Vec<u8>)> = vec![
+        (TRACE_FILE, trace_writer.0),
+        (MEMORY_FILE, memory_writer.0),
+        ("air_public_input.json", air_public_input.into_bytes()),
+        ("air_private_input.json", air_private_input.into_bytes()),
+        (CPU_AIR_PARAMS_FILE, to_json(&cpu_air_params)?),
+        (
+            CPU_AIR_PROVER_CONFIG_FILE,
+            to_json(&CpuAirProverConfig::default())?,
+        ),
+        ("oracle_transcript.json", to_json(&run_output.oracle_calls)?),
+        ("input.json", to_json(input)?),
+        ("output.json", to_json(&output)?),
+    ];
+
+    let sierra_json = serde_json::to_vec(sierra_program).map_err(io::Error::from)?;
+    let manifest = BundleManifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        sierra_sha256: sha256_hex(&sierra_json),
+        entry_function: entry_function.to_string(),
+        layout: layout.to_string(),
+        resources: run_output.resources_report(),
+        files: files
+            .iter()
+            .map(|(name, content)| {
+                let file = BundleFile {
+                    sha256: sha256_hex(content),
+                    size: content.len(),
+                };
+                (name.to_string(), file)
+            })
+            .collect(),
+    };
+
+    let mut zip_writer = ZipWriter::new(File::create(path)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in &files {
+        zip_writer
+            .start_file(*name, options)
+            .map_err(io::Error::from)?;
+        zip_writer.write_all(content)?;
+    }
+    zip_writer
+        .start_file(MANIFEST_FILE, options)
+        .map_err(io::Error::from)?;
+    zip_writer.write_all(&to_json(&manifest)?)?;
+    zip_writer.finish().map_err(io::Error::from)?;
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use cairo_io_serde::{schema::Schema, FuncArg, FuncArgs};
+    use cairo_lang_sierra::ProgramParser;
+    use cairo_proto_serde::configuration::Configuration;
+    use cairo_vm::{types::layout_name::LayoutName, Felt252};
+    use serde_json::json;
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::{run::RunOptions, run_1};
+
+    // Proof mode runs take and return an `Array<felt252>`
+    const SIERRA: &str = r#"
+        type felt252 = felt252;
+        type Array<felt252> = Array<felt252>;
+
+        libfunc array_append<felt252> = array_append<felt252>;
+        libfunc felt252_const<10> = felt252_const<10>;
+        libfunc store_temp<felt252> = store_temp<felt252>;
+        libfunc store_temp<Array<felt252>> = store_temp<Array<felt252>>;
+
+        felt252_const<10>() -> ([1]);
+        store_temp<felt252>([1]) -> ([1]);
+        array_append<felt252>([0], [1]) -> ([0]);
+        store_temp<Array<felt252>>([0]) -> ([0]);
+        return([0]);
+
+        test::main@0([0]: Array<felt252>) -> (Array<felt252>);
+    "#;
+
+    #[test]
+    fn test_write_prove_bundle() {
+        let sierra_program = ProgramParser::new().parse(SIERRA).unwrap();
+        let schema: Schema = serde_json::from_value(json!({
+            "schemas": {},
+            "cairo_input": "",
+            "cairo_output": "",
+        }))
+        .unwrap();
+        let options = RunOptions::new()
+            .layout(LayoutName::small)
+            .proof_mode(true)
+            .trace(true)
+            .memory(true)
+            .air_public_input(true)
+            .air_private_input(true);
+        let run_output = run_1(
+            &Configuration::default(),
+            &sierra_program,
+            &schema,
+            &FuncArgs(vec![FuncArg::Array(vec![Felt252::from(9)])]),
+            &options,
+        )
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.zip");
+        let input = json!([9]);
+        let manifest = write_prove_bundle(
+            &path,
+            &sierra_program,
+            "::main",
+            "small",
+            &input,
+            &run_output,
+        )
+        .unwrap();
+
+        let mut archive = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut read_file = |name: &str| {
+            let mut content = Vec::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            content
+        };
+        let manifest_json: Value = serde_json::from_slice(&read_file(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest_json, serde_json::to_value(&manifest).unwrap());
+        assert_eq!(manifest_json["layout"], "small");
+
+        let files = manifest_json["files"].as_object().unwrap();
+        assert!(files.contains_key(TRACE_FILE));
+        assert!(files.contains_key(CPU_AIR_PARAMS_FILE));
+        for (name, file) in files {
+            let content = read_file(name);
+            assert_eq!(file["sha256"], sha256_hex(&content), "{name}");
+            assert_eq!(file["size"], content.len(), "{name}");
+        }
+        let bundled_input: Value = serde_json::from_slice(&read_file("input.json")).unwrap();
+        assert_eq!(bundled_input, input);
+    }
+}
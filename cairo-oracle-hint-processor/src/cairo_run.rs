@@ -12,10 +12,11 @@ use cairo_lang_casm::{
     inline::CasmContext,
     instructions::{Instruction, InstructionBody},
 };
+use cairo_lang_runner::token_gas_cost;
 use cairo_lang_sierra::{
     extensions::{
         bitwise::BitwiseType,
-        core::{CoreLibfunc, CoreType},
+        core::{CoreLibfunc, CoreType, CoreTypeConcrete},
         ec::EcOpType,
         gas::GasBuiltinType,
         pedersen::PedersenType,
@@ -31,7 +32,7 @@ use cairo_lang_sierra::{
 };
 use cairo_lang_sierra_to_casm::{
     compiler::{CairoProgram, SierraToCasmConfig},
-    metadata::calc_metadata_ap_change_only,
+    metadata::{calc_metadata, calc_metadata_ap_change_only, MetadataComputationConfig},
 };
 use cairo_lang_sierra_type_size::get_type_size_map;
 use cairo_lang_utils::{
@@ -46,8 +47,8 @@ use cairo_vm::{
         relocatable::MaybeRelocatable,
     },
     vm::{
-        errors::{runner_errors::RunnerError, vm_errors::VirtualMachineError},
-        runners::cairo_runner::{CairoRunner, RunResources, RunnerMode},
+        errors::{trace_errors::TraceError, vm_errors::VirtualMachineError},
+        runners::cairo_runner::{CairoRunner, ResourceTracker, RunResources, RunnerMode},
         vm_core::VirtualMachine,
     },
     Felt252,
@@ -55,9 +56,15 @@ use cairo_vm::{
 use itertools::{chain, Itertools};
 use num_bigint::{BigInt, Sign};
 use num_traits::{cast::ToPrimitive, Zero};
-use std::collections::HashMap;
-
-use crate::{rpc_hint_processor::Rpc1HintProcessor, Error};
+use std::{collections::HashMap, path::Path, time::Duration};
+
+use crate::{
+    casm_cache::{self, CompiledProgram},
+    profiler::ProfilerInfo,
+    rpc_hint_processor::Rpc1HintProcessor,
+    run::{ResourceLimit, RunArtifacts, RunOutput},
+    Error,
+};
 
 /// Configuration parameters for a cairo run
 #[derive(Debug)]
@@ -79,8 +86,24 @@ pub struct Cairo1RunConfig<'a> {
     pub finalize_builtins: bool,
     /// Appends the return and input values to the output segment. This is performed by default when running in proof_mode
     pub append_return_values: bool,
+    /// Attribute the steps of the run to the Cairo functions, requires `trace_enabled`
+    pub profile: bool,
+    /// Gas available to the run, `DEFAULT_INITIAL_GAS` if not set. Only used by programs using gas
+    pub initial_gas: Option<usize>,
+    /// Stop the run after this many steps
+    pub max_steps: Option<usize>,
+    /// Stop the run once it has run for this long
+    pub timeout: Option<Duration>,
+    /// Load the compiled program from this cache directory, and store it there when missing
+    pub casm_cache_dir: Option<&'a Path>,
+    /// The entry function is a proof mode wrapper, returning the serialized output of the
+    /// function it wraps. The output is read with the schema of the wrapped function
+    pub proof_wrapper: bool,
 }
 
+/// The gas available to runs of gas-enabled programs when no initial gas is set
+pub const DEFAULT_INITIAL_GAS: usize = 9999999999999;
+
 impl Default for Cairo1RunConfig<'_> {
     fn default() -> Self {
         Self {
@@ -92,6 +115,12 @@ impl Default for Cairo1RunConfig<'_> {
             proof_mode: false,
             finalize_builtins: false,
             append_return_values: false,
+            profile: false,
+            initial_gas: None,
+            max_steps: None,
+            timeout: None,
+            casm_cache_dir: None,
+            proof_wrapper: false,
         }
     }
 }
@@ -105,74 +134,460 @@ impl Cairo1RunConfig<'_> {
 }
 
 // Runs a Cairo 1 program
-// Returns the runner & VM after execution + the return values
+// Returns the runner after execution + the run output (without artifacts)
 pub fn cairo_run_program(
     sierra_program: &SierraProgram,
     cairo_run_config: Cairo1RunConfig,
     configuration: &Configuration,
     entry_func_name: &str,
     schema: &Schema,
-) -> Result<(CairoRunner, Vec<MaybeRelocatable>, Option<String>), Error> {
-    let metadata = calc_metadata_ap_change_only(sierra_program)
-        .map_err(|_| VirtualMachineError::Unexpected)?;
-    let sierra_program_registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(sierra_program)?;
-    let type_sizes =
-        get_type_size_map(sierra_program, &sierra_program_registry).unwrap_or_default();
-    let config = SierraToCasmConfig {
-        gas_usage_check: false,
-        max_bytecode_size: usize::MAX,
-    };
-    let casm_program =
-        cairo_lang_sierra_to_casm::compiler::compile(sierra_program, &metadata, config)?;
+) -> Result<(CairoRunner, RunOutput), Error> {
+    PreparedProgram::new(sierra_program, entry_func_name, schema, &cairo_run_config)?.run(
+        cairo_run_config,
+        configuration,
+        schema,
+    )
+}
 
-    let main_func = find_function(sierra_program, entry_func_name)?;
+/// A Sierra program compiled to CASM, together with the entry code calling its entry function.
+/// Preparing doesn't depend on the arguments, so the same program can be run many times.
+pub(crate) struct PreparedProgram {
+    sierra_program_registry: ProgramRegistry<CoreType, CoreLibfunc>,
+    type_sizes: UnorderedHashMap<ConcreteTypeId, i16>,
+    main_func: Function,
+    return_type_id: Option<ConcreteTypeId>,
+    return_type_size: i16,
+    result_inner_type_size: Option<i16>,
+    builtins: Vec<BuiltinName>,
+    processor_hints: Vec<(usize, Vec<Hint>)>,
+    program: Program,
+    profiler_info: ProfilerInfo,
+    /// The gas the entry function requires before its first statement, if the program uses gas
+    required_gas: Option<usize>,
+    proof_mode: bool,
+    copy_to_output: bool,
+}
 
-    let initial_gas = 9999999999999_usize;
+impl PreparedProgram {
+    // The entry code depends on `proof_mode` and `append_return_values`,
+    // so every run of the prepared program uses the values of `cairo_run_config`
+    pub(crate) fn new(
+        sierra_program: &SierraProgram,
+        entry_func_name: &str,
+        schema: &Schema,
+        cairo_run_config: &Cairo1RunConfig,
+    ) -> Result<Self, Error> {
+        let sierra_program_registry =
+            ProgramRegistry::<CoreType, CoreLibfunc>::new(sierra_program)?;
+        let main_func = find_function(sierra_program, entry_func_name)?;
+
+        let compiled_program = match cairo_run_config.casm_cache_dir {
+            Some(cache_dir) => {
+                let key = casm_cache::cache_key(
+                    sierra_program,
+                    entry_func_name,
+                    cairo_run_config.proof_mode,
+                    cairo_run_config.copy_to_output(),
+                )?;
+                match casm_cache::load(cache_dir, &key) {
+                    Some(compiled_program) => compiled_program,
+                    None => {
+                        let compiled_program = compile_program(
+                            sierra_program,
+                            &sierra_program_registry,
+                            main_func,
+                            cairo_run_config,
+                        )?;
+                        casm_cache::store(cache_dir, &key, &compiled_program);
+                        compiled_program
+                    }
+                }
+            }
+            None => compile_program(
+                sierra_program,
+                &sierra_program_registry,
+                main_func,
+                cairo_run_config,
+            )?,
+        };
+        let CompiledProgram {
+            bytecode,
+            entry_code_size,
+            casm_size,
+            statement_offsets,
+            builtins,
+            processor_hints,
+            program_hints,
+            type_sizes,
+            required_gas,
+        } = compiled_program;
+        let type_sizes: UnorderedHashMap<ConcreteTypeId, i16> = type_sizes.into_iter().collect();
+
+        // Fetch return type data
+        let return_type_id = match main_func.signature.ret_types.last() {
+            // We need to check if the last return type is indeed the function's return value and not an implicit return value
+            return_type @ Some(concrete_ty)
+                if get_info(&sierra_program_registry, concrete_ty)
+                    .is_some_and(|info| !is_implicit_generic_id(&info.long_id.generic_id)) =>
+            {
+                return_type
+            }
+            _ => None,
+        };
 
-    // Fetch return type data
-    let return_type_id = match main_func.signature.ret_types.last() {
-        // We need to check if the last return type is indeed the function's return value and not an implicit return value
-        return_type @ Some(concrete_ty)
-            if get_info(&sierra_program_registry, concrete_ty)
-                .is_some_and(|info| !is_implicit_generic_id(&info.long_id.generic_id)) =>
-        {
-            return_type
+        if !cairo_run_config.copy_to_output() {
+            validate_schema(
+                schema,
+                main_func,
+                return_type_id,
+                &sierra_program_registry,
+                &type_sizes,
+            )?;
         }
-        _ => None,
-    };
 
-    if cairo_run_config.copy_to_output()
-        && !check_only_array_felt_input_type(
-            &main_func.signature.param_types,
+        if cairo_run_config.copy_to_output()
+            && !check_only_array_felt_input_type(
+                &main_func.signature.param_types,
+                &sierra_program_registry,
+            )
+        {
+            return Err(Error::IlegalInputValue);
+        };
+        if cairo_run_config.copy_to_output()
+            && !check_only_array_felt_return_type(return_type_id, &sierra_program_registry)
+        {
+            return Err(Error::IlegalReturnValue);
+        };
+
+        // Fetch return type data
+        let return_type_size = return_type_id
+            .and_then(|id| type_sizes.get(id).cloned())
+            .unwrap_or_default();
+        let result_inner_type_size =
+            result_inner_type_size(return_type_id, &sierra_program_registry, &type_sizes);
+
+        let profiler_info = ProfilerInfo::new(
+            sierra_program,
             &sierra_program_registry,
-        )
-    {
-        return Err(Error::IlegalInputValue);
+            statement_offsets,
+            casm_size,
+            entry_code_size,
+        );
+
+        let data: Vec<MaybeRelocatable> =
+            bytecode.into_iter().map(MaybeRelocatable::from).collect();
+
+        let program = if cairo_run_config.proof_mode {
+            Program::new_for_proof(
+                builtins.clone(),
+                data,
+                0,
+                // Proof mode is on top
+                // `jmp rel 0` is the last line of the entry code.
+                entry_code_size - 2,
+                program_hints,
+                ReferenceManager {
+                    references: Vec::new(),
+                },
+                HashMap::new(),
+                vec![],
+                None,
+            )?
+        } else {
+            Program::new(
+                builtins.clone(),
+                data,
+                Some(0),
+                program_hints,
+                ReferenceManager {
+                    references: Vec::new(),
+                },
+                HashMap::new(),
+                vec![],
+                None,
+            )?
+        };
+
+        Ok(Self {
+            main_func: main_func.clone(),
+            return_type_id: return_type_id.cloned(),
+            return_type_size,
+            result_inner_type_size,
+            builtins,
+            processor_hints,
+            program,
+            profiler_info,
+            required_gas,
+            proof_mode: cairo_run_config.proof_mode,
+            copy_to_output: cairo_run_config.copy_to_output(),
+            sierra_program_registry,
+            type_sizes,
+        })
+    }
+
+    // Runs the prepared program with the arguments and run flags of `cairo_run_config`
+    // Returns the runner after execution + the run output (without artifacts)
+    pub(crate) fn run(
+        &self,
+        cairo_run_config: Cairo1RunConfig,
+        configuration: &Configuration,
+        schema: &Schema,
+    ) -> Result<(CairoRunner, RunOutput), Error> {
+        check_arguments_size(
+            &self.main_func,
+            &self.sierra_program_registry,
+            &self.type_sizes,
+            cairo_run_config.args,
+        )?;
+        let available_gas = cairo_run_config.initial_gas.unwrap_or(DEFAULT_INITIAL_GAS);
+        // The cost of the entry function's first statements is paid before calling it
+        let initial_gas = match self.required_gas {
+            Some(required_gas) => {
+                available_gas
+                    .checked_sub(required_gas)
+                    .ok_or(Error::NotEnoughGasToCall {
+                        required: required_gas,
+                        available: available_gas,
+                    })?
+            }
+            None => available_gas,
+        };
+        let builtin_count: i16 = self.builtins.len().into_or_panic();
+
+        let run_resources = cairo_run_config
+            .max_steps
+            .map(RunResources::new)
+            .unwrap_or_default();
+        let hint_processor =
+            Cairo1HintProcessor::new(&self.processor_hints, run_resources, self.copy_to_output);
+        let mut hint_processor = Rpc1HintProcessor::new(hint_processor, configuration)?
+            .with_timeout(cairo_run_config.timeout);
+
+        let runner_mode = if self.proof_mode {
+            RunnerMode::ProofModeCairo1
+        } else {
+            RunnerMode::ExecutionMode
+        };
+
+        let mut runner = CairoRunner::new_v2(
+            &self.program,
+            cairo_run_config.layout,
+            runner_mode,
+            cairo_run_config.trace_enabled,
+        )?;
+        let end = runner.initialize(self.proof_mode)?;
+        load_arguments(&mut runner, &cairo_run_config, &self.main_func, initial_gas)?;
+
+        // Run it until the end / infinite loop in proof_mode
+        match runner.run_until_pc(end, &mut hint_processor) {
+            // An oracle call cut short by the deadline fails its hint, so any error of a timed out
+            // run is reported as the timeout
+            Err(err) if hint_processor.consumed() => {
+                let limit = match (
+                    hint_processor.timed_out(),
+                    cairo_run_config.timeout,
+                    cairo_run_config.max_steps,
+                    err,
+                ) {
+                    (true, Some(timeout), _, _) => ResourceLimit::Timeout(timeout),
+                    (false, _, Some(max_steps), VirtualMachineError::UnfinishedExecution) => {
+                        ResourceLimit::MaxSteps(max_steps)
+                    }
+                    (_, _, _, err) => return Err(err.into()),
+                };
+                runner.vm.segments.compute_effective_sizes();
+                return Err(Error::ResourceLimitExceeded {
+                    limit,
+                    resources: runner.get_execution_resources()?,
+                });
+            }
+            result => result?,
+        }
+        if self.proof_mode {
+            runner.run_for_steps(1, &mut hint_processor)?;
+        }
+
+        runner.end_run(false, false, &mut hint_processor)?;
+        let resources = runner.get_execution_resources()?;
+        let segment_sizes = runner.vm.segments.compute_effective_sizes().clone();
+        let oracle_calls = hint_processor.into_oracle_calls();
+
+        // Fetch return values
+        let (return_values, panic_data) = match fetch_return_values(
+            self.return_type_size,
+            self.result_inner_type_size,
+            &runner.vm,
+            builtin_count,
+            self.copy_to_output,
+        ) {
+            Ok(return_values) => (return_values, None),
+            Err(Error::RunPanic(panic_data)) => (Vec::new(), Some(panic_data)),
+            Err(err) => return Err(err),
+        };
+        let gas_consumed = if self.required_gas.is_some() && !self.copy_to_output {
+            fetch_remaining_gas(
+                &self.main_func.signature.ret_types,
+                &self.sierra_program_registry,
+                &self.type_sizes,
+                &runner.vm,
+                builtin_count,
+            )?
+            .map(|remaining_gas| available_gas.saturating_sub(remaining_gas))
+        } else {
+            None
+        };
+
+        let (serialized_values, serialized_output) =
+            if cairo_run_config.serialize_output && panic_data.is_none() {
+                if self.copy_to_output {
+                    let serialized: Vec<Felt252> = return_values
+                        .iter()
+                        .map(|value| value.get_int().unwrap_or_default())
+                        .collect();
+                    // Skip array_len
+                    let values = serialized
+                        .get(1..)
+                        .ok_or(Error::FailedToExtractReturnValues)?;
+                    // The returned array holds the serialized output, as in execution mode, or
+                    // the serialized output of the function wrapped by a proof wrapper
+                    let output = if cairo_run_config.proof_wrapper || schema.has_output() {
+                        process_output(values.to_vec(), schema)?
+                    } else {
+                        // The return value is already serialized, so we can just print the array values
+                        raw_output(values)
+                    };
+                    (serialized, Some(output))
+                } else {
+                    let serialized = serialize_output(
+                        &return_values,
+                        &mut runner.vm,
+                        self.return_type_id.as_ref(),
+                        &self.sierra_program_registry,
+                        &self.type_sizes,
+                    )?;
+                    let output = if schema.has_output() {
+                        process_output(serialized.clone(), schema)?
+                    } else {
+                        // A returned `Array<felt252>` has no output schema, its values are printed
+                        raw_output(&serialized)
+                    };
+                    (serialized, Some(output))
+                }
+            } else {
+                (Vec::new(), None)
+            };
+
+        // Set stop pointers for builtins so we can obtain the air public input
+        if cairo_run_config.finalize_builtins {
+            if self.copy_to_output {
+                // Set stop pointer for each builtin
+                runner.vm.builtins_final_stack_from_stack_pointer_dict(
+                    &self
+                        .builtins
+                        .iter()
+                        .enumerate()
+                        .map(|(i, builtin)| {
+                            (
+                                *builtin,
+                                (runner.vm.get_ap() - (self.builtins.len() - 1 - i)).unwrap(),
+                            )
+                        })
+                        .collect(),
+                    false,
+                )?;
+            } else {
+                finalize_builtins(
+                    &self.main_func.signature.ret_types,
+                    &self.type_sizes,
+                    &mut runner.vm,
+                    builtin_count,
+                )?;
+            }
+
+            // Build execution public memory
+            if self.proof_mode {
+                runner.finalize_segments()?;
+            }
+        }
+
+        runner.relocate(true)?;
+
+        let profile = if cairo_run_config.profile {
+            let trace = runner
+                .relocated_trace
+                .as_ref()
+                .ok_or(Error::Trace(TraceError::TraceNotRelocated))?;
+            let program_base = runner.vm.segments.relocate_segments()?[0];
+            Some(self.profiler_info.profile(trace, program_base))
+        } else {
+            None
+        };
+
+        let run_output = RunOutput {
+            output: serialized_output,
+            return_values: serialized_values,
+            panic_data,
+            gas_consumed,
+            resources,
+            segment_sizes,
+            oracle_calls,
+            profile,
+            artifacts: RunArtifacts::default(),
+        };
+        Ok((runner, run_output))
+    }
+}
+
+/// Compiles `sierra_program` to CASM and assembles it with the entry code calling `main_func`.
+fn compile_program(
+    sierra_program: &SierraProgram,
+    sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    main_func: &Function,
+    cairo_run_config: &Cairo1RunConfig,
+) -> Result<CompiledProgram, Error> {
+    // Programs compiled with gas enabled declare the GasBuiltin type
+    let uses_gas = sierra_program
+        .type_declarations
+        .iter()
+        .any(|declaration| declaration.long_id.generic_id == GasBuiltinType::ID);
+    let metadata = if uses_gas {
+        calc_metadata(sierra_program, MetadataComputationConfig::default())?
+    } else {
+        calc_metadata_ap_change_only(sierra_program).map_err(|_| VirtualMachineError::Unexpected)?
     };
-    if cairo_run_config.copy_to_output()
-        && !check_only_array_felt_return_type(return_type_id, &sierra_program_registry)
-    {
-        return Err(Error::IlegalReturnValue);
+    let type_sizes = get_type_size_map(sierra_program, sierra_program_registry).unwrap_or_default();
+    let config = SierraToCasmConfig {
+        gas_usage_check: uses_gas,
+        max_bytecode_size: usize::MAX,
     };
+    let casm_program =
+        cairo_lang_sierra_to_casm::compiler::compile(sierra_program, &metadata, config)?;
+
+    let required_gas = metadata
+        .gas_info
+        .function_costs
+        .get(&main_func.id)
+        .map(|costs| {
+            costs
+                .iter()
+                .map(|(token_type, cost)| {
+                    cost.into_or_panic::<usize>() * token_gas_cost(*token_type)
+                })
+                .sum()
+        });
 
     // Modified entry code to be compatible with custom cairo1 Proof Mode.
     // This adds code that's needed for dictionaries, adjusts ap for builtin pointers, adds initial gas for the gas builtin if needed, and sets up other necessary code for cairo1
     let (entry_code, builtins) = create_entry_code(
-        &sierra_program_registry,
+        sierra_program_registry,
         &casm_program,
         &type_sizes,
         main_func,
-        &cairo_run_config,
+        cairo_run_config,
     )?;
 
-    // Fetch return type data
-    let return_type_size = return_type_id
-        .and_then(|id| type_sizes.get(id).cloned())
-        .unwrap_or_default();
-
     // This footer is used by lib funcs
     let libfunc_footer = create_code_footer();
-    let builtin_count: i16 = builtins.len().into_or_panic();
 
     // This is the program we are actually running/proving
     // With (embedded proof mode), cairo1 header and the libfunc footer
@@ -184,144 +599,42 @@ pub fn cairo_run_program(
 
     let (processor_hints, program_hints) = build_hints_vec(instructions.clone());
 
-    let hint_processor = Cairo1HintProcessor::new(
-        &processor_hints,
-        RunResources::default(),
-        cairo_run_config.copy_to_output(),
-    );
-    let mut hint_processor = Rpc1HintProcessor::new(hint_processor, configuration)?;
-
-    let data: Vec<MaybeRelocatable> = instructions
+    let bytecode = instructions
         .flat_map(|inst| inst.assemble().encode())
         .map(|x| Felt252::from(&x))
-        .map(MaybeRelocatable::from)
         .collect();
 
-    let program = if cairo_run_config.proof_mode {
-        Program::new_for_proof(
-            builtins.clone(),
-            data,
-            0,
-            // Proof mode is on top
-            // `jmp rel 0` is the last line of the entry code.
-            entry_code.current_code_offset - 2,
-            program_hints,
-            ReferenceManager {
-                references: Vec::new(),
-            },
-            HashMap::new(),
-            vec![],
-            None,
-        )?
-    } else {
-        Program::new(
-            builtins.clone(),
-            data,
-            Some(0),
-            program_hints,
-            ReferenceManager {
-                references: Vec::new(),
-            },
-            HashMap::new(),
-            vec![],
-            None,
-        )?
-    };
-
-    let runner_mode = if cairo_run_config.proof_mode {
-        RunnerMode::ProofModeCairo1
-    } else {
-        RunnerMode::ExecutionMode
-    };
-
-    let mut runner = CairoRunner::new_v2(
-        &program,
-        cairo_run_config.layout,
-        runner_mode,
-        cairo_run_config.trace_enabled,
-    )?;
-    let end = runner.initialize(cairo_run_config.proof_mode)?;
-    load_arguments(&mut runner, &cairo_run_config, main_func, initial_gas)?;
-
-    // Run it until the end / infinite loop in proof_mode
-    runner.run_until_pc(end, &mut hint_processor)?;
-    if cairo_run_config.proof_mode {
-        runner.run_for_steps(1, &mut hint_processor)?;
-    }
-
-    runner.end_run(false, false, &mut hint_processor)?;
-
-    let result_inner_type_size =
-        result_inner_type_size(return_type_id, &sierra_program_registry, &type_sizes);
-    // Fetch return values
-    let return_values = fetch_return_values(
-        return_type_size,
-        result_inner_type_size,
-        &runner.vm,
-        builtin_count,
-        cairo_run_config.copy_to_output(),
-    )?;
-
-    let serialized_output = if cairo_run_config.serialize_output {
-        if cairo_run_config.copy_to_output() {
-            // The return value is already serialized, so we can just print the array values
-            let mut output_string = String::from("[");
-            // Skip array_len
-            for elem in return_values[1..].iter() {
-                maybe_add_whitespace(&mut output_string);
-                output_string.push_str(&elem.to_string());
-            }
-            output_string.push(']');
-            Some(output_string)
-        } else {
-            let serialized = serialize_output(
-                &return_values,
-                &mut runner.vm,
-                return_type_id,
-                &sierra_program_registry,
-                &type_sizes,
-            );
-            Some(process_output(serialized, schema).expect("Process output failed"))
-        }
-    } else {
-        None
-    };
-
-    // Set stop pointers for builtins so we can obtain the air public input
-    if cairo_run_config.finalize_builtins {
-        if cairo_run_config.copy_to_output() {
-            // Set stop pointer for each builtin
-            runner.vm.builtins_final_stack_from_stack_pointer_dict(
-                &builtins
-                    .iter()
-                    .enumerate()
-                    .map(|(i, builtin)| {
-                        (
-                            *builtin,
-                            (runner.vm.get_ap() - (builtins.len() - 1 - i)).unwrap(),
-                        )
-                    })
-                    .collect(),
-                false,
-            )?;
-        } else {
-            finalize_builtins(
-                &main_func.signature.ret_types,
-                &type_sizes,
-                &mut runner.vm,
-                builtin_count,
-            )?;
-        }
-
-        // Build execution public memory
-        if cairo_run_config.proof_mode {
-            runner.finalize_segments()?;
-        }
-    }
+    let sierra_statement_info = &casm_program.debug_info.sierra_statement_info;
+    let statement_offsets = sierra_statement_info
+        .iter()
+        .map(|info| info.start_offset)
+        .collect();
+    let casm_size = sierra_statement_info
+        .last()
+        .map(|info| info.end_offset)
+        .unwrap_or_default();
 
-    runner.relocate(true)?;
+    // Type ids aren't ordered, so the sizes are listed in declaration order
+    let type_sizes = sierra_program
+        .type_declarations
+        .iter()
+        .filter_map(|declaration| {
+            let size = type_sizes.get(&declaration.id)?;
+            Some((declaration.id.clone(), *size))
+        })
+        .collect();
 
-    Ok((runner, return_values, serialized_output))
+    Ok(CompiledProgram {
+        bytecode,
+        entry_code_size: entry_code.current_code_offset,
+        casm_size,
+        statement_offsets,
+        builtins,
+        processor_hints,
+        program_hints,
+        type_sizes,
+        required_gas,
+    })
 }
 
 #[allow(clippy::type_complexity)]
@@ -357,7 +670,7 @@ fn build_hints_vec<'b>(
 fn find_function<'a>(
     sierra_program: &'a SierraProgram,
     name_suffix: &'a str,
-) -> Result<&'a Function, RunnerError> {
+) -> Result<&'a Function, Error> {
     sierra_program
         .funcs
         .iter()
@@ -368,7 +681,7 @@ fn find_function<'a>(
                 false
             }
         })
-        .ok_or_else(|| RunnerError::MissingMain)
+        .ok_or_else(|| Error::FunctionNotFound(name_suffix.to_string()))
 }
 
 /// Creates a list of instructions that will be appended to the program's bytecode.
@@ -605,20 +918,6 @@ fn create_entry_code(
             expected_arguments_size += ty_size;
         }
     }
-    let actual_args_size = config
-        .args
-        .iter()
-        .map(|arg| match arg {
-            FuncArg::Single(_) => 1,
-            FuncArg::Array(_) => 2,
-        })
-        .sum::<i16>();
-    if expected_arguments_size != actual_args_size {
-        return Err(Error::ArgumentsSizeMismatch {
-            expected: expected_arguments_size,
-            actual: actual_args_size,
-        });
-    }
 
     casm_build_extend!(ctx, let () = call FUNCTION;);
 
@@ -707,7 +1006,7 @@ fn create_entry_code(
 
             EndOutputCopy:
         };
-        if !actual_args_size.is_zero() {
+        if !expected_arguments_size.is_zero() {
             // Serialize the input values into the output segment
             // We lost the output_ptr var after re-scoping, so we need to create it again
             // The last instruction will write the last output ptr so we can find it in [ap - 1]
@@ -866,6 +1165,43 @@ fn get_info<'a>(
         .map(|ctc| ctc.info())
 }
 
+// The entry code only advances AP over the arguments, which are loaded before each run,
+// so their size is checked against the signature when running
+fn check_arguments_size(
+    func: &Function,
+    sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    type_sizes: &UnorderedHashMap<ConcreteTypeId, i16>,
+    args: &[FuncArg],
+) -> Result<(), Error> {
+    let (_, builtin_offset) = get_function_builtins(&func.signature.param_types, false);
+    let mut expected_arguments_size = 0;
+    for ty in &func.signature.param_types {
+        let info = get_info(sierra_program_registry, ty)
+            .ok_or_else(|| Error::NoInfoForType(ty.clone()))?;
+        let generic_ty = &info.long_id.generic_id;
+        // Builtins, the segment arena, the system and the gas are provided by the entry code
+        if !builtin_offset.contains_key(generic_ty)
+            && ![SegmentArenaType::ID, SystemType::ID, GasBuiltinType::ID].contains(generic_ty)
+        {
+            expected_arguments_size += type_sizes[ty];
+        }
+    }
+    let actual_args_size = args
+        .iter()
+        .map(|arg| match arg {
+            FuncArg::Single(_) => 1,
+            FuncArg::Array(_) => 2,
+        })
+        .sum::<i16>();
+    if expected_arguments_size != actual_args_size {
+        return Err(Error::ArgumentsSizeMismatch {
+            expected: expected_arguments_size,
+            actual: actual_args_size,
+        });
+    }
+    Ok(())
+}
+
 fn get_function_builtins(
     params: &[cairo_lang_sierra::ids::ConcreteTypeId],
     append_output: bool,
@@ -900,6 +1236,114 @@ fn get_function_builtins(
     (builtins, builtin_offset)
 }
 
+// Checks that the felt layout described by the schema matches the function params and return type
+// Reports every mismatching field so that the schema can be fixed before running the program
+fn validate_schema(
+    schema: &Schema,
+    func: &Function,
+    return_type_id: Option<&ConcreteTypeId>,
+    sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    type_sizes: &UnorderedHashMap<ConcreteTypeId, i16>,
+) -> Result<(), Error> {
+    let mut mismatches = Vec::new();
+
+    if let Some(input_layout) = schema.input_layout().map_err(Error::InvalidSchema)? {
+        let params = func
+            .signature
+            .param_types
+            .iter()
+            .filter(|ty| {
+                get_info(sierra_program_registry, ty)
+                    .is_some_and(|info| !is_implicit_generic_id(&info.long_id.generic_id))
+            })
+            .collect_vec();
+        let param_sizes = params
+            .iter()
+            .map(|ty| type_sizes.get(ty).copied().unwrap_or_default())
+            .collect_vec();
+        compare_layout(
+            "input",
+            &input_layout,
+            &params,
+            &param_sizes,
+            &mut mismatches,
+        );
+    }
+
+    if let Some(output_layout) = schema.output_layout().map_err(Error::InvalidSchema)? {
+        let return_type =
+            result_inner_type(return_type_id, sierra_program_registry).or(return_type_id);
+        match return_type {
+            None if !output_layout.is_empty() => mismatches.push(format!(
+                "output: schema expects {} fields but the function has no return value",
+                output_layout.len()
+            )),
+            None => {}
+            // The returned array holds the serialized output, which any schema may describe
+            Some(_)
+                if check_only_array_felt_return_type(return_type_id, sierra_program_registry) => {}
+            Some(return_type) => {
+                let return_type_size = type_sizes.get(return_type).copied().unwrap_or_default();
+                let output_size: i16 = output_layout.iter().map(|(_, size)| size).sum();
+                // A single field may describe the whole return value
+                let single_field_matches =
+                    output_layout.len() == 1 && output_size == return_type_size;
+                if !single_field_matches {
+                    // Otherwise each field describes a member of the returned struct or tuple
+                    let members = match sierra_program_registry.get_type(return_type) {
+                        Ok(CoreTypeConcrete::Struct(info)) => info.members.iter().collect_vec(),
+                        _ => vec![return_type],
+                    };
+                    let member_sizes = members
+                        .iter()
+                        .map(|ty| type_sizes.get(ty).copied().unwrap_or_default())
+                        .collect_vec();
+                    compare_layout(
+                        "output",
+                        &output_layout,
+                        &members,
+                        &member_sizes,
+                        &mut mismatches,
+                    );
+                }
+            }
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::SchemaMismatch(mismatches))
+    }
+}
+
+fn compare_layout(
+    kind: &str,
+    layout: &[(String, i16)],
+    types: &[&ConcreteTypeId],
+    sizes: &[i16],
+    mismatches: &mut Vec<String>,
+) {
+    if layout.len() != types.len() {
+        mismatches.push(format!(
+            "{}: schema has {} fields but the function signature has {}",
+            kind,
+            layout.len(),
+            types.len()
+        ));
+    }
+    for (i, ((name, size), (ty, expected))) in
+        layout.iter().zip(types.iter().zip(sizes)).enumerate()
+    {
+        if size != expected {
+            mismatches.push(format!(
+                "{} field {} `{}`: schema describes {} felts but Sierra type {} takes {}",
+                kind, i, name, size, ty, expected
+            ));
+        }
+    }
+}
+
 // Checks that the program input (if present) is of type Array<Felt252>
 fn check_only_array_felt_input_type(
     params: &[ConcreteTypeId],
@@ -1102,6 +1546,37 @@ fn fetch_return_values(
     Ok(return_values)
 }
 
+// Returns the gas left when the entry function returned, if it takes the gas builtin
+fn fetch_remaining_gas(
+    main_ret_types: &[ConcreteTypeId],
+    sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+    type_sizes: &UnorderedHashMap<ConcreteTypeId, i16>,
+    vm: &VirtualMachine,
+    builtin_count: i16,
+) -> Result<Option<usize>, Error> {
+    let ret_types_sizes = main_ret_types
+        .iter()
+        .map(|id| type_sizes.get(id).cloned().unwrap_or_default());
+    let full_ret_types_size: i16 = ret_types_sizes.clone().sum();
+    // The return values are followed by the final builtin pointers written by the entry code
+    let mut ptr = (vm.get_ap() - (full_ret_types_size + builtin_count) as usize)
+        .map_err(VirtualMachineError::Math)?;
+
+    for (id, size) in main_ret_types.iter().zip(ret_types_sizes) {
+        if get_info(sierra_program_registry, id)
+            .is_some_and(|info| info.long_id.generic_id == GasBuiltinType::ID)
+        {
+            let remaining_gas = vm
+                .get_integer(ptr)?
+                .to_usize()
+                .ok_or(Error::FailedToExtractReturnValues)?;
+            return Ok(Some(remaining_gas));
+        }
+        ptr.offset += size as usize;
+    }
+    Ok(None)
+}
+
 // Calculates builtins' final_stack setting each stop_ptr
 // Calling this function is a must if either air_public_input or cairo_pie are needed
 fn finalize_builtins(
@@ -1149,8 +1624,131 @@ fn finalize_builtins(
     Ok(())
 }
 
+// Prints serialized values which have no output schema as they are
+fn raw_output(values: &[Felt252]) -> String {
+    let mut output_string = String::from("[");
+    for value in values {
+        maybe_add_whitespace(&mut output_string);
+        output_string.push_str(&value.to_string());
+    }
+    output_string.push(']');
+    output_string
+}
+
 fn maybe_add_whitespace(string: &mut String) {
     if !string.is_empty() && !string.ends_with('[') && !string.ends_with('{') {
         string.push(' ');
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cairo_lang_sierra::ProgramParser;
+    use serde_json::json;
+
+    use super::*;
+
+    const SIERRA: &str = r#"
+        type felt252 = felt252;
+        type u256 = Struct<ut@core::integer::u256, felt252, felt252>;
+        type Array<felt252> = Array<felt252>;
+        type Output = Struct<ut@Output, felt252, u256>;
+
+        return([0]);
+
+        felt_main@0([0]: felt252) -> (felt252);
+        array_main@0([0]: Array<felt252>) -> (Array<felt252>);
+        struct_main@0([0]: Array<felt252>) -> (Output);
+    "#;
+
+    fn schema(output: serde_json::Value) -> Schema {
+        serde_json::from_value(json!({
+            "schemas": {
+                "Output": {
+                    "fields": [
+                        {"a": {"type": "Primitive", "name": "felt252"}},
+                        {"b": {"type": "Primitive", "name": "u256"}},
+                    ],
+                },
+            },
+            "cairo_input": "Output",
+            "cairo_output": output,
+        }))
+        .unwrap()
+    }
+
+    fn validate(schema: &Schema, function: &str) -> Result<(), Error> {
+        let program = ProgramParser::new().parse(SIERRA).unwrap();
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(&program).unwrap();
+        let type_sizes = get_type_size_map(&program, &registry).unwrap();
+        let func = program
+            .funcs
+            .iter()
+            .find(|func| func.id.debug_name.as_deref() == Some(function))
+            .unwrap();
+        validate_schema(
+            schema,
+            func,
+            func.signature.ret_types.last(),
+            &registry,
+            &type_sizes,
+        )
+    }
+
+    fn mismatches(result: Result<(), Error>) -> Vec<String> {
+        match result {
+            Err(Error::SchemaMismatch(mismatches)) => mismatches,
+            result => panic!("Expected a schema mismatch, got {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_validate_schema_felt_array_accepts_any_output() {
+        assert!(validate(&schema(json!("Output")), "array_main").is_ok());
+        assert!(validate(&schema(json!("")), "array_main").is_ok());
+    }
+
+    #[test]
+    fn test_validate_schema_struct_output() {
+        assert!(validate(&schema(json!("Output")), "struct_main").is_ok());
+    }
+
+    #[test]
+    fn test_validate_schema_mismatches() {
+        let mismatches = mismatches(validate(&schema(json!("Output")), "felt_main"));
+        assert_eq!(
+            mismatches,
+            vec![
+                "input field 0 `Output`: schema describes 2 felts but Sierra type felt252 takes 1",
+                "output: schema has 2 fields but the function signature has 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_layout() {
+        let felt = ConcreteTypeId::from_string("felt252");
+        let u256 = ConcreteTypeId::from_string("u256");
+        let layout = [("a".to_string(), 1), ("b".to_string(), 2)];
+
+        let mut mismatches = Vec::new();
+        compare_layout("input", &layout, &[&felt, &u256], &[1, 2], &mut mismatches);
+        assert!(mismatches.is_empty());
+
+        compare_layout("input", &layout, &[&u256, &felt], &[2, 1], &mut mismatches);
+        assert_eq!(
+            mismatches,
+            vec![
+                "input field 0 `a`: schema describes 1 felts but Sierra type u256 takes 2",
+                "input field 1 `b`: schema describes 2 felts but Sierra type felt252 takes 1",
+            ]
+        );
+
+        mismatches.clear();
+        compare_layout("output", &layout, &[&felt], &[1], &mut mismatches);
+        assert_eq!(
+            mismatches,
+            vec!["output: schema has 2 fields but the function signature has 1"]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use cairo_lang_casm::hints::Hint;
+use cairo_lang_sierra::{ids::ConcreteTypeId, program::Program as SierraProgram};
+use cairo_vm::{serde::deserialize_program::HintParams, types::builtin_name::BuiltinName, Felt252};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+
+use crate::Error;
+
+/// The version of the format of the cache entries, to bump whenever [`CompiledProgram`] or the
+/// way it is assembled changes.
+const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// The version of the Sierra to CASM compiler, which must match the `cairo-lang-sierra-to-casm`
+/// pin of the workspace `Cargo.toml`.
+const SIERRA_TO_CASM_VERSION: &str = "2.7.0";
+
+/// A Sierra program compiled to CASM, assembled with the entry code calling its entry function.
+///
+/// This is everything a [`PreparedProgram`](crate::cairo_run::PreparedProgram) needs from the
+/// compilation, so that it can be stored in the CASM cache.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CompiledProgram {
+    /// The assembled entry code, CASM program and libfunc footer
+    pub(crate) bytecode: Vec<Felt252>,
+    /// The size of the entry code placed before the CASM program
+    pub(crate) entry_code_size: usize,
+    /// The size of the CASM program, the libfunc footer comes after it
+    pub(crate) casm_size: usize,
+    /// The CASM start offset of each Sierra statement
+    pub(crate) statement_offsets: Vec<usize>,
+    pub(crate) builtins: Vec<BuiltinName>,
+    pub(crate) processor_hints: Vec<(usize, Vec<Hint>)>,
+    pub(crate) program_hints: HashMap<usize, Vec<HintParams>>,
+    pub(crate) type_sizes: Vec<(ConcreteTypeId, i16)>,
+    /// The gas the entry function requires before its first statement, if the program uses gas
+    pub(crate) required_gas: Option<usize>,
+}
+
+/// Returns the cache key of `sierra_program` compiled to call `entry_func_name`.
+///
+/// The entry code depends on `proof_mode` and `copy_to_output`, and the CASM on the version of
+/// the Sierra to CASM compiler, so they are part of the key, together with the versions of this
+/// crate and of the cache format.
+pub(crate) fn cache_key(
+    sierra_program: &SierraProgram,
+    entry_func_name: &str,
+    proof_mode: bool,
+    copy_to_output: bool,
+) -> Result<String, Error> {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update(CACHE_FORMAT_VERSION.to_le_bytes());
+    hasher.update(SIERRA_TO_CASM_VERSION);
+    serde_json::to_writer(&mut hasher, sierra_program).map_err(io::Error::from)?;
+    hasher.update(entry_func_name);
+    hasher.update([proof_mode as u8, copy_to_output as u8]);
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn entry_path(cache_dir: &Path, key: &str) -> PathBuf {
+    cache_dir.join(format!("{key}.json"))
+}
+
+/// Returns the program stored under `key`. Unreadable entries are treated as missing.
+pub(crate) fn load(cache_dir: &Path, key: &str) -> Option<CompiledProgram> {
+    let file = fs::File::open(entry_path(cache_dir, key)).ok()?;
+    serde_json::from_reader(BufReader::new(file)).ok()
+}
+
+/// Stores `program` under `key`.
+/// The cache only saves compilation time, so failing to write it doesn't fail the run.
+pub(crate) fn store(cache_dir: &Path, key: &str, program: &CompiledProgram) {
+    // Entries are written to a uniquely named temporary file first so that concurrent runs never
+    // read a partially written entry. The file is removed if it isn't persisted.
+    let _ = fs::create_dir_all(cache_dir).and_then(|_| {
+        let tmp_file = NamedTempFile::new_in(cache_dir)?;
+        let mut writer = BufWriter::new(tmp_file);
+        serde_json::to_writer(&mut writer, program)?;
+        let tmp_file = writer.into_inner().map_err(|err| err.into_error())?;
+        tmp_file
+            .persist(entry_path(cache_dir, key))
+            .map_err(|err| err.error)?;
+        Ok(())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use cairo_lang_sierra::ProgramParser;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn program(sierra: &str) -> SierraProgram {
+        ProgramParser::new().parse(sierra).unwrap()
+    }
+
+    fn compiled_program() -> CompiledProgram {
+        CompiledProgram {
+            bytecode: vec![Felt252::from(1), Felt252::from(2)],
+            entry_code_size: 1,
+            casm_size: 1,
+            statement_offsets: vec![0],
+            builtins: vec![BuiltinName::range_check],
+            processor_hints: Vec::new(),
+            program_hints: HashMap::new(),
+            type_sizes: vec![(ConcreteTypeId::from_string("felt252"), 1)],
+            required_gas: None,
+        }
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let sierra_program = program("type felt252 = felt252;");
+        let key = cache_key(&sierra_program, "::main", false, false).unwrap();
+
+        assert_eq!(
+            cache_key(&sierra_program, "::main", false, false).unwrap(),
+            key
+        );
+        for other_key in [
+            cache_key(&sierra_program, "::other", false, false),
+            cache_key(&sierra_program, "::main", true, false),
+            cache_key(&sierra_program, "::main", false, true),
+            cache_key(&program("type u8 = u8;"), "::main", false, false),
+        ] {
+            assert_ne!(other_key.unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn test_load_miss() {
+        let cache_dir = TempDir::new().unwrap();
+
+        assert!(load(cache_dir.path(), "key").is_none());
+        // The cache directory is created by the first store
+        assert!(load(&cache_dir.path().join("missing"), "key").is_none());
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let cache_dir = TempDir::new().unwrap();
+        let cache_dir = cache_dir.path().join("casm-cache");
+        store(&cache_dir, "key", &compiled_program());
+
+        let loaded = load(&cache_dir, "key").unwrap();
+        let expected = compiled_program();
+        assert_eq!(loaded.bytecode, expected.bytecode);
+        assert_eq!(loaded.builtins, expected.builtins);
+        assert_eq!(loaded.type_sizes, expected.type_sizes);
+        assert!(load(&cache_dir, "other").is_none());
+    }
+
+    #[test]
+    fn test_store_leaves_no_tmp_file() {
+        let cache_dir = TempDir::new().unwrap();
+        store(cache_dir.path(), "key", &compiled_program());
+        // A second store replaces the entry
+        store(cache_dir.path(), "key", &compiled_program());
+
+        let files = fs::read_dir(cache_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(files, vec!["key.json"]);
+    }
+
+    #[test]
+    fn test_concurrent_stores() {
+        let cache_dir = TempDir::new().unwrap();
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| store(cache_dir.path(), "key", &compiled_program()));
+            }
+        });
+
+        assert!(load(cache_dir.path(), "key").is_some());
+        assert_eq!(fs::read_dir(cache_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_load_invalid_entry() {
+        let cache_dir = TempDir::new().unwrap();
+        fs::write(entry_path(cache_dir.path(), "key"), "{\"bytecode\":").unwrap();
+
+        assert!(load(cache_dir.path(), "key").is_none());
+    }
+}
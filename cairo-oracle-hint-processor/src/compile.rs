@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use cairo_lang_compiler::{
+    compile_prepared_db_program, db::RootDatabase, diagnostics::DiagnosticsReporter,
+    project::setup_project, CompilerConfig,
+};
+use cairo_lang_sierra::program::Program as SierraProgram;
+
+use crate::Error;
+
+/// Compiles the Cairo project at `path` to Sierra in-process, without Scarb.
+///
+/// `path` is either a directory with a `cairo_project.toml` file or a single `.cairo` file.
+/// Like Scarb's `enable-gas`, `enable_gas` adds the gas withdrawals that make the program metered.
+pub fn compile_cairo_project(path: &Path, enable_gas: bool) -> Result<SierraProgram, Error> {
+    let mut builder = RootDatabase::builder();
+    builder.detect_corelib();
+    if !enable_gas {
+        builder.skip_auto_withdraw_gas();
+    }
+    let mut db = builder
+        .build()
+        .map_err(|e| Error::SierraCompilation(e.to_string()))?;
+
+    let main_crate_ids = setup_project(&mut db, path)
+        .map_err(|e| Error::SierraCompilation(format!("{}: {}", path.display(), e)))?;
+
+    let mut diagnostics = String::new();
+    let compiler_config = CompilerConfig {
+        diagnostics_reporter: DiagnosticsReporter::write_to_string(&mut diagnostics)
+            .with_crates(&main_crate_ids)
+            .allow_warnings(),
+        // The entry function is found by its name
+        replace_ids: true,
+        ..CompilerConfig::default()
+    };
+    compile_prepared_db_program(&mut db, main_crate_ids, compiler_config).map_err(|e| {
+        if diagnostics.is_empty() {
+            Error::SierraCompilation(e.to_string())
+        } else {
+            Error::SierraCompilation(diagnostics)
+        }
+    })
+}
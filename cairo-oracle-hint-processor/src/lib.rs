@@ -1,11 +1,12 @@
 use std::io;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::Path;
 
 use anyhow::Result;
 use bincode::enc::write::Writer;
+use cairo_io_serde::cairo_output::SerializeOutputError;
 use cairo_io_serde::schema::Schema;
-use cairo_io_serde::FuncArgs;
+use cairo_io_serde::{FuncArgs, IoSerdeError};
 use cairo_lang_sierra::ids::ConcreteTypeId;
 use cairo_lang_sierra::program::Program as SierraProgram;
 use cairo_lang_sierra::program_registry::ProgramRegistryError;
@@ -16,19 +17,35 @@ use cairo_run::Cairo1RunConfig;
 use cairo_vm::air_public_input::PublicInputError;
 use cairo_vm::cairo_run::EncodeTraceError;
 use cairo_vm::types::errors::program_errors::ProgramError;
-use cairo_vm::types::layout_name::LayoutName;
 use cairo_vm::vm::errors::memory_errors::MemoryError;
 use cairo_vm::vm::errors::runner_errors::RunnerError;
 use cairo_vm::vm::errors::trace_errors::TraceError;
 use cairo_vm::vm::errors::vm_errors::VirtualMachineError;
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
 use cairo_vm::Felt252;
 use thiserror::Error;
 
+pub mod agent_runner;
+pub mod bundle;
 pub mod cairo_run;
+pub mod compile;
+pub mod profiler;
+pub mod prover_config;
 pub mod rpc_hint_processor;
+pub mod run;
 
+mod casm_cache;
 mod hint_processor_utils;
 
+pub use agent_runner::AgentRunner;
+pub use bundle::{write_prove_bundle, BundleManifest};
+pub use compile::compile_cairo_project;
+pub use profiler::Profile;
+pub use prover_config::{cpu_air_params, write_prover_config, CpuAirParams};
+pub use run::{
+    OracleCallsReport, ResourceLimit, ResourcesReport, RunArtifacts, RunOptions, RunOutput,
+};
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Invalid arguments")]
@@ -57,8 +74,22 @@ pub enum Error {
     Program(#[from] ProgramError),
     #[error(transparent)]
     Memory(#[from] MemoryError),
+    #[error(transparent)]
+    SerializeOutput(#[from] SerializeOutputError),
+    #[error(transparent)]
+    IoSerde(#[from] IoSerdeError),
     #[error("Program panicked with {0:?}")]
     RunPanic(Vec<Felt252>),
+    #[error("Run exceeded its {limit} after {} steps", .resources.n_steps)]
+    ResourceLimitExceeded {
+        limit: ResourceLimit,
+        /// The resources used until the run was stopped
+        resources: ExecutionResources,
+    },
+    #[error("Not enough gas to call function: {required} required, {available} available")]
+    NotEnoughGasToCall { required: usize, available: usize },
+    #[error("No function found with a name ending with {0}")]
+    FunctionNotFound(String),
     #[error("Function signature has no return types")]
     NoRetTypesInSignature,
     #[error("No size for concrete type id: {0}")]
@@ -76,10 +107,14 @@ pub enum Error {
         param_index: usize,
         arg_index: usize,
     },
-    #[error("Only programs returning `Array<Felt252>` can be currently proven. Try serializing the final values before returning them")]
+    #[error("Only programs returning `Array<Felt252>` can be currently proven. Try serializing the final values before returning them, or generate a wrapper with `scarb agent-generate --proof-wrapper`")]
     IlegalReturnValue,
-    #[error("Only programs with `Array<Felt252>` as an input can be currently proven. Try inputing the serialized version of the input and deserializing it on main")]
+    #[error("Only programs with `Array<Felt252>` as an input can be currently proven. Try inputing the serialized version of the input and deserializing it on main, or generate a wrapper with `scarb agent-generate --proof-wrapper`")]
     IlegalInputValue,
+    #[error("Invalid schema: {0}")]
+    InvalidSchema(String),
+    #[error("Schema does not match the function signature:\n{}", .0.join("\n"))]
+    SchemaMismatch(Vec<String>),
     #[error("Configuration error: {0}")]
     ConfigError(String),
     #[error("Servers configuration file error: {0}")]
@@ -121,90 +156,125 @@ impl FileWriter {
 
 pub fn run_1(
     configuration: &Configuration,
-    layout: &LayoutName,
-    trace_file: &Option<PathBuf>,
-    memory_file: &Option<PathBuf>,
-    cairo_pie_output: &Option<PathBuf>,
-    air_public_input: &Option<PathBuf>,
-    air_private_input: &Option<PathBuf>,
-    args: &FuncArgs,
-    schema: &Schema,
     sierra_program: &SierraProgram,
-    entry_func_name: &str,
-    proof_mode: bool,
-) -> Result<Option<String>, Error> {
+    schema: &Schema,
+    args: &FuncArgs,
+    options: &RunOptions,
+) -> Result<RunOutput, Error> {
+    if options.air_private_input_file.is_some()
+        && (options.trace_file.is_none() || options.memory_file.is_none())
+    {
+        return Err(Error::ConfigError(
+            "Writing the AIR private input requires the trace and memory files".to_string(),
+        ));
+    }
+
     let cairo_run_config = Cairo1RunConfig {
-        proof_mode: proof_mode,
+        proof_mode: options.proof_mode,
         serialize_output: true,
-        relocate_mem: memory_file.is_some(), //|| air_public_input.is_some(),
-        layout: *layout,
-        trace_enabled: trace_file.is_some(), //|| args.air_public_input.is_some(),
+        // The AIR public input is built from the relocated trace and memory
+        relocate_mem: options.memory || options.air_public_input,
+        layout: options.layout,
+        trace_enabled: options.trace || options.air_public_input || options.profile,
         args: &args.0,
-        finalize_builtins: cairo_pie_output.is_some(),
+        // The AIR public input needs the builtin segments and the public memory to be finalized
+        finalize_builtins: options.cairo_pie || options.air_public_input,
         append_return_values: false,
+        profile: options.profile,
+        initial_gas: options.initial_gas,
+        max_steps: options.max_steps,
+        timeout: options.timeout,
+        casm_cache_dir: options.casm_cache_dir.as_deref(),
+        proof_wrapper: options.proof_wrapper,
     };
 
-    let (runner, _vm, return_values) = cairo_run::cairo_run_program(
-        &sierra_program,
+    let (mut runner, mut run_output) = cairo_run::cairo_run_program(
+        sierra_program,
         cairo_run_config,
         configuration,
-        entry_func_name,
-        schema
+        &options.entry_func_name,
+        schema,
     )?;
+    let artifacts = &mut run_output.artifacts;
 
-    if let Some(file_path) = air_public_input {
+    if options.air_public_input {
         let json = runner.get_air_public_input()?.serialize_json()?;
-        std::fs::write(file_path, json)?;
+        if let Some(file_path) = &options.air_public_input_file {
+            std::fs::write(file_path, &json)?;
+            if options.prover_config {
+                // The prover needs its parameters, which depend on the trace length, next to it
+                write_prover_config(file_path.parent().unwrap_or(Path::new("")), &json)?;
+            }
+        }
+        artifacts.air_public_input = Some(json);
     }
 
-    if let (Some(file_path), Some(trace_file), Some(memory_file)) =
-        (air_private_input, trace_file.clone(), memory_file.clone())
-    {
-        // Get absolute paths of trace_file & memory_file
-        let trace_path = trace_file
-            .as_path()
-            .canonicalize()
-            .unwrap_or(trace_file.clone())
-            .to_string_lossy()
-            .to_string();
-        let memory_path = memory_file
-            .as_path()
-            .canonicalize()
-            .unwrap_or(memory_file.clone())
-            .to_string_lossy()
-            .to_string();
-
-        let json = runner
-            .get_air_private_input()
-            .to_serializable(trace_path, memory_path)
-            .serialize_json()
-            .map_err(PublicInputError::Serde)?;
-        std::fs::write(file_path, json)?;
+    if options.air_private_input {
+        let air_private_input = runner.get_air_private_input();
+        if let (Some(file_path), Some(trace_file), Some(memory_file)) = (
+            &options.air_private_input_file,
+            &options.trace_file,
+            &options.memory_file,
+        ) {
+            // Get absolute paths of trace_file & memory_file
+            let trace_path = trace_file
+                .as_path()
+                .canonicalize()
+                .unwrap_or(trace_file.clone())
+                .to_string_lossy()
+                .to_string();
+            let memory_path = memory_file
+                .as_path()
+                .canonicalize()
+                .unwrap_or(memory_file.clone())
+                .to_string_lossy()
+                .to_string();
+
+            let json = air_private_input
+                .clone()
+                .to_serializable(trace_path, memory_path)
+                .serialize_json()
+                .map_err(PublicInputError::Serde)?;
+            std::fs::write(file_path, json)?;
+        }
+        artifacts.air_private_input = Some(air_private_input);
     }
 
-    if let Some(ref file_path) = cairo_pie_output {
-        runner.get_cairo_pie()?.write_zip_file(file_path)?
+    if options.cairo_pie {
+        let cairo_pie = runner.get_cairo_pie()?;
+        if let Some(file_path) = &options.cairo_pie_file {
+            cairo_pie.write_zip_file(file_path)?
+        }
+        artifacts.cairo_pie = Some(cairo_pie);
     }
 
-    if let Some(trace_path) = trace_file {
+    if options.trace {
         let relocated_trace = runner
             .relocated_trace
+            .take()
             .ok_or(Error::Trace(TraceError::TraceNotRelocated))?;
-        let trace_file = std::fs::File::create(trace_path)?;
-        let mut trace_writer =
-            FileWriter::new(io::BufWriter::with_capacity(3 * 1024 * 1024, trace_file));
+        if let Some(trace_path) = &options.trace_file {
+            let trace_file = std::fs::File::create(trace_path)?;
+            let mut trace_writer =
+                FileWriter::new(io::BufWriter::with_capacity(3 * 1024 * 1024, trace_file));
 
-        cairo_vm::cairo_run::write_encoded_trace(&relocated_trace, &mut trace_writer)?;
-        trace_writer.flush()?;
+            cairo_vm::cairo_run::write_encoded_trace(&relocated_trace, &mut trace_writer)?;
+            trace_writer.flush()?;
+        }
+        artifacts.trace = Some(relocated_trace);
     }
-    if let Some(memory_path) = memory_file {
-        let memory_file = std::fs::File::create(memory_path)?;
-        let mut memory_writer =
-            FileWriter::new(io::BufWriter::with_capacity(5 * 1024 * 1024, memory_file));
+    if options.memory {
+        let relocated_memory = std::mem::take(&mut runner.relocated_memory);
+        if let Some(memory_path) = &options.memory_file {
+            let memory_file = std::fs::File::create(memory_path)?;
+            let mut memory_writer =
+                FileWriter::new(io::BufWriter::with_capacity(5 * 1024 * 1024, memory_file));
 
-        cairo_vm::cairo_run::write_encoded_memory(&runner.relocated_memory, &mut memory_writer)?;
-        memory_writer.flush()?;
+            cairo_vm::cairo_run::write_encoded_memory(&relocated_memory, &mut memory_writer)?;
+            memory_writer.flush()?;
+        }
+        artifacts.memory = Some(relocated_memory);
     }
 
-    Ok(return_values)
+    Ok(run_output)
 }
@@ -0,0 +1,337 @@
+use std::collections::{BTreeMap, HashMap};
+
+use cairo_lang_sierra::{
+    extensions::{
+        core::{CoreConcreteLibfunc, CoreLibfunc, CoreType, CoreTypeConcrete},
+        lib_func::ConcreteLibfunc,
+    },
+    program::{GenStatement, Program as SierraProgram},
+    program_registry::ProgramRegistry,
+};
+use cairo_vm::vm::trace::trace_entry::RelocatedTraceEntry;
+use itertools::Itertools;
+
+/// The VM steps and builtin usage of a run, attributed to the Cairo functions.
+///
+/// Stacks are lists of function names, outermost function first.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    /// VM steps spent in each function stack
+    pub steps: HashMap<Vec<String>, usize>,
+    /// Libfunc invocations taking each builtin, by builtin and function stack
+    pub builtins: BTreeMap<String, HashMap<Vec<String>, usize>>,
+}
+
+impl Profile {
+    /// Returns the steps in the folded stacks format read by flamegraph tools.
+    pub fn folded_steps(&self) -> String {
+        folded(&self.steps)
+    }
+
+    /// Returns the usage of `builtin` in the folded stacks format read by flamegraph tools.
+    pub fn folded_builtin(&self, builtin: &str) -> Option<String> {
+        self.builtins.get(builtin).map(folded)
+    }
+}
+
+fn folded(weights: &HashMap<Vec<String>, usize>) -> String {
+    weights
+        .iter()
+        .map(|(stack, weight)| format!("{} {}\n", stack.join(";"), weight))
+        .sorted()
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatementKind {
+    FunctionCall,
+    Return,
+    Other,
+}
+
+#[derive(Debug)]
+struct StatementInfo {
+    function_idx: usize,
+    kind: StatementKind,
+    builtins: Vec<&'static str>,
+}
+
+/// Maps the program counter of a prepared program to its Sierra statements and functions.
+#[derive(Debug)]
+pub(crate) struct ProfilerInfo {
+    function_names: Vec<String>,
+    /// The CASM start offset of each Sierra statement
+    statement_offsets: Vec<usize>,
+    statements: Vec<StatementInfo>,
+    /// The size of the entry code placed before the CASM program
+    entry_code_size: usize,
+    /// The size of the CASM program, the libfunc footer comes after it
+    bytecode_len: usize,
+}
+
+impl ProfilerInfo {
+    pub(crate) fn new(
+        sierra_program: &SierraProgram,
+        sierra_program_registry: &ProgramRegistry<CoreType, CoreLibfunc>,
+        statement_offsets: Vec<usize>,
+        bytecode_len: usize,
+        entry_code_size: usize,
+    ) -> Self {
+        let function_names = sierra_program
+            .funcs
+            .iter()
+            .map(|func| func.id.to_string())
+            .collect();
+
+        // Each statement belongs to the function with the last entry point at or before it
+        let mut entry_points = sierra_program
+            .funcs
+            .iter()
+            .enumerate()
+            .map(|(func_idx, func)| (func.entry_point.0, func_idx))
+            .collect::<Vec<_>>();
+        entry_points.sort_unstable();
+
+        let statements = sierra_program
+            .statements
+            .iter()
+            .enumerate()
+            .map(|(idx, statement)| {
+                let entry_point_idx = entry_points
+                    .partition_point(|(entry_point, _)| *entry_point <= idx)
+                    .saturating_sub(1);
+                let function_idx = entry_points[entry_point_idx].1;
+                let (kind, builtins) = match statement {
+                    GenStatement::Return(_) => (StatementKind::Return, vec![]),
+                    GenStatement::Invocation(invocation) => {
+                        match sierra_program_registry.get_libfunc(&invocation.libfunc_id) {
+                            Ok(CoreConcreteLibfunc::FunctionCall(_)) => {
+                                (StatementKind::FunctionCall, vec![])
+                            }
+                            // Moving a builtin pointer around doesn't use the builtin
+                            Ok(CoreConcreteLibfunc::Mem(_)) | Err(_) => {
+                                (StatementKind::Other, vec![])
+                            }
+                            Ok(libfunc) => (
+                                StatementKind::Other,
+                                libfunc
+                                    .param_signatures()
+                                    .iter()
+                                    .filter_map(|param| {
+                                        builtin_name(
+                                            sierra_program_registry.get_type(&param.ty).ok()?,
+                                        )
+                                    })
+                                    .collect(),
+                            ),
+                        }
+                    }
+                };
+                StatementInfo {
+                    function_idx,
+                    kind,
+                    builtins,
+                }
+            })
+            .collect();
+
+        Self {
+            function_names,
+            statement_offsets,
+            statements,
+            entry_code_size,
+            bytecode_len,
+        }
+    }
+
+    /// Attributes each step of the relocated trace to the stack of functions running it.
+    /// `program_base` is the relocated address of the program segment.
+    pub(crate) fn profile(&self, trace: &[RelocatedTraceEntry], program_base: usize) -> Profile {
+        let code_start = program_base + self.entry_code_size;
+        // The function indices of the current stack, the running function last
+        let mut stack: Vec<usize> = Vec::new();
+        let mut steps = HashMap::<Vec<usize>, usize>::new();
+        let mut builtins = BTreeMap::<&str, HashMap<Vec<usize>, usize>>::new();
+
+        for entry in trace {
+            // Skip the entry code and the libfunc footer
+            let Some(pc) = entry.pc.checked_sub(code_start) else {
+                continue;
+            };
+            if pc >= self.bytecode_len {
+                continue;
+            }
+
+            // The first statement is always at offset 0, so the partition index is > 0
+            let statement_idx = self
+                .statement_offsets
+                .partition_point(|offset| *offset <= pc)
+                - 1;
+            let statement = &self.statements[statement_idx];
+            match stack.last_mut() {
+                Some(function_idx) => *function_idx = statement.function_idx,
+                None => stack.push(statement.function_idx),
+            }
+            add_weight(&mut steps, &stack);
+
+            // Only count the first instruction of each statement
+            if pc != self.statement_offsets[statement_idx] {
+                continue;
+            }
+            for builtin in &statement.builtins {
+                add_weight(builtins.entry(*builtin).or_default(), &stack);
+            }
+            match statement.kind {
+                // The callee is set by its first step
+                StatementKind::FunctionCall => stack.push(statement.function_idx),
+                StatementKind::Return => {
+                    stack.pop();
+                }
+                StatementKind::Other => {}
+            }
+        }
+
+        Profile {
+            steps: self.named_stacks(steps),
+            builtins: builtins
+                .into_iter()
+                .map(|(builtin, weights)| (builtin.to_string(), self.named_stacks(weights)))
+                .collect(),
+        }
+    }
+
+    fn named_stacks(&self, weights: HashMap<Vec<usize>, usize>) -> HashMap<Vec<String>, usize> {
+        weights
+            .into_iter()
+            .map(|(stack, weight)| {
+                let names = stack
+                    .iter()
+                    .map(|idx| self.function_names[*idx].clone())
+                    .collect();
+                (names, weight)
+            })
+            .collect()
+    }
+}
+
+fn add_weight(weights: &mut HashMap<Vec<usize>, usize>, stack: &[usize]) {
+    match weights.get_mut(stack) {
+        Some(weight) => *weight += 1,
+        None => {
+            weights.insert(stack.to_vec(), 1);
+        }
+    }
+}
+
+fn builtin_name(ty: &CoreTypeConcrete) -> Option<&'static str> {
+    match ty {
+        CoreTypeConcrete::RangeCheck(_) => Some("range_check"),
+        CoreTypeConcrete::RangeCheck96(_) => Some("range_check96"),
+        CoreTypeConcrete::Pedersen(_) => Some("pedersen"),
+        CoreTypeConcrete::Poseidon(_) => Some("poseidon"),
+        CoreTypeConcrete::Bitwise(_) => Some("bitwise"),
+        CoreTypeConcrete::EcOp(_) => Some("ec_op"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cairo_lang_sierra::ProgramParser;
+
+    use super::*;
+
+    const SIERRA: &str = r#"
+        type felt252 = felt252;
+        type Bitwise = Bitwise;
+        type u128 = u128;
+
+        libfunc felt252_add = felt252_add;
+        libfunc call_helper = function_call<user@helper>;
+        libfunc bitwise = bitwise;
+
+        felt252_add([0], [1]) -> ([2]);
+        call_helper([3], [4], [5]) -> ([3]);
+        return([2]);
+        bitwise([0], [1], [2]) -> ([0], [1], [2], [3]);
+        return([0]);
+
+        main@0([0]: felt252, [1]: felt252, [3]: Bitwise, [4]: u128, [5]: u128) -> (felt252);
+        helper@3([0]: Bitwise, [1]: u128, [2]: u128) -> (Bitwise);
+    "#;
+
+    // The program segment starts at 1, after an entry code of 3 instructions
+    const PROGRAM_BASE: usize = 1;
+    const ENTRY_CODE_SIZE: usize = 3;
+
+    fn profiler_info() -> ProfilerInfo {
+        profiler_info_of(&ProgramParser::new().parse(SIERRA).unwrap())
+    }
+
+    fn profiler_info_of(program: &SierraProgram) -> ProfilerInfo {
+        let registry = ProgramRegistry::<CoreType, CoreLibfunc>::new(program).unwrap();
+        // felt252_add takes 2 instructions, bitwise 4 and the others 1
+        ProfilerInfo::new(program, &registry, vec![0, 2, 3, 4, 8], 9, ENTRY_CODE_SIZE)
+    }
+
+    fn trace(pcs: &[usize]) -> Vec<RelocatedTraceEntry> {
+        pcs.iter()
+            .map(|pc| RelocatedTraceEntry {
+                pc: *pc,
+                ap: 0,
+                fp: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_profile() {
+        // The entry code, main until the call, helper, main after the call and the footer
+        let profile = profiler_info().profile(
+            &trace(&[1, 2, 3, 4, 5, 6, 8, 9, 10, 11, 12, 7, 13]),
+            PROGRAM_BASE,
+        );
+
+        assert_eq!(
+            profile.steps,
+            HashMap::from([
+                (vec!["main".to_string()], 4),
+                (vec!["main".to_string(), "helper".to_string()], 5),
+            ])
+        );
+        assert_eq!(profile.folded_steps(), "main 4\nmain;helper 5\n");
+        assert_eq!(
+            profile.folded_builtin("bitwise").as_deref(),
+            Some("main;helper 1\n")
+        );
+        assert_eq!(profile.folded_builtin("range_check"), None);
+    }
+
+    #[test]
+    fn test_profile_counts_builtins_once_per_statement() {
+        // helper runs twice, each run of bitwise uses the builtin once
+        let profile = profiler_info().profile(
+            &trace(&[4, 5, 6, 8, 9, 10, 11, 12, 6, 8, 9, 10, 11, 12, 7]),
+            PROGRAM_BASE,
+        );
+
+        assert_eq!(profile.folded_steps(), "main 5\nmain;helper 10\n");
+        assert_eq!(
+            profile.folded_builtin("bitwise").as_deref(),
+            Some("main;helper 2\n")
+        );
+    }
+
+    #[test]
+    fn test_profile_unsorted_functions() {
+        // The functions may be declared in any order
+        let mut program = ProgramParser::new().parse(SIERRA).unwrap();
+        program.funcs.reverse();
+        let profile = profiler_info_of(&program).profile(
+            &trace(&[1, 2, 3, 4, 5, 6, 8, 9, 10, 11, 12, 7, 13]),
+            PROGRAM_BASE,
+        );
+
+        assert_eq!(profile.folded_steps(), "main 4\nmain;helper 5\n");
+    }
+}
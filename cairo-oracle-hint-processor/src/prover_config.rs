@@ -0,0 +1,246 @@
+use std::path::Path;
+
+use cairo_vm::air_public_input::PublicInputError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::Error;
+
+/// The file of the STARK parameters, written next to the AIR public input.
+pub const CPU_AIR_PARAMS_FILE: &str = "cpu_air_params.json";
+/// The file of the prover configuration, written next to the AIR public input.
+pub const CPU_AIR_PROVER_CONFIG_FILE: &str = "cpu_air_prover_config.json";
+
+// Every Cairo layout uses 16 trace rows for each CPU step
+const LOG_CPU_COMPONENT_HEIGHT: u32 = 4;
+// FRI folds at most 2^4 evaluations into one per layer
+const MAX_FRI_STEP: u32 = 4;
+const LOG_LAST_LAYER_DEGREE_BOUND: u32 = 6;
+
+/// The STARK parameters of a proof, the `cpu_air_params.json` of the Stone prover.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuAirParams {
+    pub field: String,
+    pub stark: StarkParams,
+    pub use_extension_field: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StarkParams {
+    pub fri: FriParams,
+    /// The log of the blowup factor of the low degree extension
+    pub log_n_cosets: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FriParams {
+    /// The log of the number of evaluations folded by each FRI layer
+    pub fri_step_list: Vec<u32>,
+    pub last_layer_degree_bound: u64,
+    pub n_queries: u32,
+    pub proof_of_work_bits: u32,
+}
+
+/// The prover configuration, the `cpu_air_prover_config.json` of the Stone prover.
+/// It only affects the speed and memory use of the prover, not the proof.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuAirProverConfig {
+    pub cached_lde_config: CachedLdeConfig,
+    pub constraint_polynomial_task_size: u32,
+    pub n_out_of_memory_merkle_layers: u32,
+    pub table_prover_n_tasks_per_segment: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedLdeConfig {
+    pub store_full_lde: bool,
+    pub use_fft_for_eval: bool,
+}
+
+impl Default for CpuAirProverConfig {
+    fn default() -> Self {
+        Self {
+            cached_lde_config: CachedLdeConfig {
+                store_full_lde: false,
+                use_fft_for_eval: false,
+            },
+            constraint_polynomial_task_size: 256,
+            n_out_of_memory_merkle_layers: 1,
+            table_prover_n_tasks_per_segment: 32,
+        }
+    }
+}
+
+/// Returns the STARK parameters of the run described by `air_public_input`.
+///
+/// FRI has to reduce the trace polynomial to the last layer, so the FRI steps and the log of the
+/// last layer degree bound add up to the log of the trace length. The trace length is the number
+/// of steps of the public input times the rows of a CPU step in its layout.
+pub fn cpu_air_params(air_public_input: &str) -> Result<CpuAirParams, Error> {
+    let public_input: Value =
+        serde_json::from_str(air_public_input).map_err(PublicInputError::Serde)?;
+    let n_steps = public_input["n_steps"]
+        .as_u64()
+        .filter(|n_steps| *n_steps > 0)
+        .ok_or_else(|| Error::ConfigError("The AIR public input has no steps".to_string()))?;
+    let layout = public_input["layout"].as_str().unwrap_or_default();
+    let log_trace_length = n_steps.next_power_of_two().ilog2()
+        + LOG_CPU_COMPONENT_HEIGHT
+        + log_cpu_component_step(layout, &public_input["dynamic_params"])?;
+
+    let log_last_layer_degree_bound = log_trace_length.min(LOG_LAST_LAYER_DEGREE_BOUND);
+    let fri_degree = log_trace_length - log_last_layer_degree_bound;
+    // The first layer is the trace polynomial itself
+    let mut fri_step_list = vec![0];
+    let n_full_steps = (fri_degree / MAX_FRI_STEP) as usize;
+    fri_step_list.extend(vec![MAX_FRI_STEP; n_full_steps]);
+    if fri_degree % MAX_FRI_STEP != 0 {
+        fri_step_list.push(fri_degree % MAX_FRI_STEP);
+    }
+
+    Ok(CpuAirParams {
+        field: "PrimeField0".to_string(),
+        stark: StarkParams {
+            fri: FriParams {
+                fri_step_list,
+                last_layer_degree_bound: 1 << log_last_layer_degree_bound,
+                // 18 queries with a blowup of 16 and 24 bits of proof of work give 96 bits
+                // of security
+                n_queries: 18,
+                proof_of_work_bits: 24,
+            },
+            log_n_cosets: 4,
+        },
+        use_extension_field: false,
+    })
+}
+
+// The CPU component of the static layouts takes one step per component instance, the dynamic
+// layout gives its own in its parameters
+fn log_cpu_component_step(layout: &str, dynamic_params: &Value) -> Result<u32, Error> {
+    match layout {
+        "plain"
+        | "small"
+        | "dex"
+        | "recursive"
+        | "starknet"
+        | "starknet_with_keccak"
+        | "recursive_large_output"
+        | "recursive_with_poseidon"
+        | "all_solidity"
+        | "all_cairo" => Ok(0),
+        "dynamic" => match dynamic_params["cpu_component_step"].as_u64() {
+            Some(step) if step.is_power_of_two() => Ok(step.ilog2()),
+            Some(step) => Err(Error::ConfigError(format!(
+                "The CPU component step {step} is not a power of two"
+            ))),
+            None => Ok(0),
+        },
+        _ => Err(Error::ConfigError(format!(
+            "The AIR public input has an unknown layout {layout:?}"
+        ))),
+    }
+}
+
+/// Writes the STARK parameters of the run described by `air_public_input` and the prover
+/// configuration to [`CPU_AIR_PARAMS_FILE`] and [`CPU_AIR_PROVER_CONFIG_FILE`] in `dir`.
+pub fn write_prover_config(dir: &Path, air_public_input: &str) -> Result<(), Error> {
+    let params = serde_json::to_string_pretty(&cpu_air_params(air_public_input)?)
+        .map_err(PublicInputError::Serde)?;
+    std::fs::write(dir.join(CPU_AIR_PARAMS_FILE), params)?;
+    let prover_config = serde_json::to_string_pretty(&CpuAirProverConfig::default())
+        .map_err(PublicInputError::Serde)?;
+    std::fs::write(dir.join(CPU_AIR_PROVER_CONFIG_FILE), prover_config)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn public_input(layout: &str, n_steps: u64, dynamic_params: Value) -> String {
+        json!({
+            "layout": layout,
+            "n_steps": n_steps,
+            "dynamic_params": dynamic_params,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_cpu_air_params() {
+        // (layout, n_steps, cpu_component_step, fri_step_list, last_layer_degree_bound)
+        let cases = [
+            // Traces shorter than the last layer are the last layer
+            ("plain", 1, None, vec![0], 16),
+            ("small", 3, None, vec![0], 64),
+            ("recursive", 4, None, vec![0], 64),
+            // Exact multiples of MAX_FRI_STEP
+            ("all_cairo", 64, None, vec![0, 4], 64),
+            ("starknet", 1024, None, vec![0, 4, 4], 64),
+            // The remainder makes the last step
+            ("all_cairo", 100, None, vec![0, 4, 1], 64),
+            ("dynamic", 1 << 20, None, vec![0, 4, 4, 4, 4, 2], 64),
+            // The dynamic layout takes more rows per step
+            ("dynamic", 64, Some(1), vec![0, 4], 64),
+            ("dynamic", 64, Some(2), vec![0, 4, 1], 64),
+            ("dynamic", 64, Some(16), vec![0, 4, 4], 64),
+            ("dynamic", 1, Some(4), vec![0], 64),
+        ];
+
+        for (layout, n_steps, cpu_component_step, fri_step_list, last_layer_degree_bound) in cases {
+            let dynamic_params = match cpu_component_step {
+                Some(step) => json!({ "cpu_component_step": step }),
+                None => Value::Null,
+            };
+            let params = cpu_air_params(&public_input(layout, n_steps, dynamic_params)).unwrap();
+            let fri = &params.stark.fri;
+            assert_eq!(
+                fri.fri_step_list, fri_step_list,
+                "{layout} with {n_steps} steps"
+            );
+            assert_eq!(
+                fri.last_layer_degree_bound, last_layer_degree_bound,
+                "{layout} with {n_steps} steps"
+            );
+        }
+    }
+
+    #[test]
+    fn test_cpu_air_params_errors() {
+        let cases = [
+            public_input("unknown", 64, Value::Null),
+            public_input("dynamic", 64, json!({ "cpu_component_step": 3 })),
+            public_input("all_cairo", 0, Value::Null),
+            json!({ "layout": "all_cairo" }).to_string(),
+        ];
+
+        for air_public_input in cases {
+            assert!(
+                matches!(
+                    cpu_air_params(&air_public_input),
+                    Err(Error::ConfigError(_))
+                ),
+                "{air_public_input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_prover_config() {
+        let dir = tempfile::tempdir().unwrap();
+        write_prover_config(dir.path(), &public_input("all_cairo", 64, Value::Null)).unwrap();
+
+        let params: CpuAirParams =
+            serde_json::from_slice(&std::fs::read(dir.path().join(CPU_AIR_PARAMS_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(params.stark.fri.fri_step_list, vec![0, 4]);
+        let prover_config: CpuAirProverConfig = serde_json::from_slice(
+            &std::fs::read(dir.path().join(CPU_AIR_PROVER_CONFIG_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(prover_config, CpuAirProverConfig::default());
+    }
+}
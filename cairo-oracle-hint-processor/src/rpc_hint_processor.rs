@@ -26,13 +26,31 @@ use cairo_vm::{
 };
 use core::any::Any;
 use reqwest::Url;
+use serde::Serialize;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
+/// An oracle call made by a run, with its wall time.
+#[derive(Debug, Clone, Serialize)]
+pub struct OracleCall {
+    pub selector: String,
+    pub request: Value,
+    pub response: Value,
+    pub duration: Duration,
+}
+
+// The number of steps between two checks of the run deadline
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+
 /// HintProcessor for Cairo 1 compiler hints.
 pub struct Rpc1HintProcessor<'a> {
     inner_processor: Cairo1HintProcessor,
     configuration: &'a Configuration,
+    oracle_calls: Vec<OracleCall>,
+    deadline: Option<Instant>,
+    timed_out: bool,
+    n_steps: usize,
 }
 
 impl<'a> Rpc1HintProcessor<'a> {
@@ -43,9 +61,71 @@ impl<'a> Rpc1HintProcessor<'a> {
         Ok(Self {
             inner_processor,
             configuration,
+            oracle_calls: Vec::new(),
+            deadline: None,
+            timed_out: false,
+            n_steps: 0,
         })
     }
 
+    /// Stops the run once `timeout` has elapsed from now.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.deadline = timeout.map(|timeout| Instant::now() + timeout);
+        self
+    }
+
+    /// Returns true if the run was stopped by its timeout.
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Returns the oracle calls made so far, in call order.
+    pub fn oracle_calls(&self) -> &[OracleCall] {
+        &self.oracle_calls
+    }
+
+    pub fn into_oracle_calls(self) -> Vec<OracleCall> {
+        self.oracle_calls
+    }
+
+    /// Marks the run as timed out if its deadline has passed.
+    fn check_deadline(&mut self) {
+        self.timed_out = self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline);
+    }
+
+    /// Returns `timeout` capped to the time left before the run deadline, so that a blocking
+    /// oracle request can't outlive the run. Fails once the deadline has passed.
+    fn oracle_timeout(&mut self, timeout: Duration) -> Result<Duration, HintError> {
+        let Some(deadline) = self.deadline else {
+            return Ok(timeout);
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            self.timed_out = true;
+            return Err(HintError::CustomHint(Box::from(
+                "The run timed out before the oracle call".to_string(),
+            )));
+        }
+        Ok(timeout.min(remaining))
+    }
+
+    fn log_oracle_call(
+        &mut self,
+        selector: &str,
+        request: Value,
+        response: Value,
+        started: Instant,
+    ) {
+        self.oracle_calls.push(OracleCall {
+            selector: selector.to_string(),
+            request,
+            response,
+            duration: started.elapsed(),
+        });
+    }
+
     /// Executes a cheatcode.
     fn execute_cheatcode(
         &mut self,
@@ -100,13 +180,14 @@ impl<'a> Rpc1HintProcessor<'a> {
             .expect("cannot be a base URL")
             .push(selector);
 
-        let data = deserialize_cairo_serde(
+        let request = deserialize_cairo_serde(
             self.configuration,
             &configuration.input,
             &mut inputs.as_ref(),
         );
 
         let use_polling = server_config.polling.unwrap_or(false);
+        let started = Instant::now();
 
         if use_polling {
             let default_polling_config = PollingConfig {
@@ -129,6 +210,7 @@ impl<'a> Rpc1HintProcessor<'a> {
                 })?;
 
             let max_attempts = polling_config.max_attempts;
+            let request_timeout = Duration::from_secs(polling_config.request_timeout);
             let polling_interval = Duration::from_secs(polling_config.polling_interval);
             let start_time = Instant::now();
             let overall_timeout = Duration::from_secs(polling_config.overall_timeout);
@@ -136,7 +218,8 @@ impl<'a> Rpc1HintProcessor<'a> {
             // Initial request to start the job
             let response = client
                 .post(server_url.clone())
-                .json(&data)
+                .json(&request)
+                .timeout(self.oracle_timeout(request_timeout)?)
                 .header("x-admin-api-key", "qwerty")
                 .send()
                 .map_err(|e| {
@@ -187,12 +270,16 @@ impl<'a> Rpc1HintProcessor<'a> {
 
                 println!("Checking status at URL: {}", status_url);
 
-                let status_response = client.get(status_url.clone()).send().map_err(|e| {
-                    HintError::CustomHint(Box::from(format!(
-                        "Failed to send status request: {}",
-                        e
-                    )))
-                })?;
+                let status_response = client
+                    .get(status_url.clone())
+                    .timeout(self.oracle_timeout(request_timeout)?)
+                    .send()
+                    .map_err(|e| {
+                        HintError::CustomHint(Box::from(format!(
+                            "Failed to send status request: {}",
+                            e
+                        )))
+                    })?;
 
                 let status_body = status_response.text().map_err(|e| {
                     HintError::CustomHint(Box::from(format!(
@@ -248,21 +335,22 @@ impl<'a> Rpc1HintProcessor<'a> {
                             )))
                         })?;
 
+                        self.log_oracle_call(selector, request, output.clone(), started);
                         return Ok(());
                     }
                 } else {
                     println!("Job not completed. Current status: {:?}", status_json);
                 }
 
-                std::thread::sleep(polling_interval);
+                std::thread::sleep(self.oracle_timeout(polling_interval)?);
                 attempt += 1;
             }
         } else {
             let client = reqwest::blocking::Client::new();
             let response = client
                 .post(server_url.clone())
-                .json(&data)
-                .timeout(Duration::from_secs(240))
+                .json(&request)
+                .timeout(self.oracle_timeout(Duration::from_secs(240))?)
                 .header("x-admin-api-key", "qwerty")
                 .send()
                 .map_err(|e| {
@@ -313,6 +401,8 @@ impl<'a> Rpc1HintProcessor<'a> {
                     e
                 )))
             })?;
+
+            self.log_oracle_call(selector, request, output, started);
         }
 
         Ok(())
@@ -357,13 +447,18 @@ impl<'a> HintProcessorLogic for Rpc1HintProcessor<'a> {
                     output_start,
                     output_end,
                 }) => {
-                    self.execute_cheatcode(
+                    let result = self.execute_cheatcode(
                         selector,
                         [input_start, input_end],
                         [output_start, output_end],
                         vm,
                         exec_scopes,
-                    )?;
+                    );
+                    // An oracle request cut short by the run deadline fails the hint
+                    if result.is_err() {
+                        self.check_deadline();
+                    }
+                    result?;
                 }
                 _ => {
                     self.inner_processor.execute(vm, exec_scopes, hint)?;
@@ -376,11 +471,16 @@ impl<'a> HintProcessorLogic for Rpc1HintProcessor<'a> {
 
 impl<'a> ResourceTracker for Rpc1HintProcessor<'a> {
     fn consumed(&self) -> bool {
-        self.inner_processor.consumed()
+        self.timed_out || self.inner_processor.consumed()
     }
 
     fn consume_step(&mut self) {
-        self.inner_processor.consume_step()
+        self.inner_processor.consume_step();
+        self.n_steps += 1;
+        // Reading the clock on every step would slow the run down
+        if self.n_steps % DEADLINE_CHECK_INTERVAL == 0 {
+            self.check_deadline();
+        }
     }
 
     fn get_n_steps(&self) -> Option<usize> {
@@ -471,3 +571,38 @@ impl<'a> MemBuffer<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cairo_vm::vm::runners::cairo_runner::RunResources;
+
+    use super::*;
+
+    fn hint_processor(configuration: &Configuration) -> Rpc1HintProcessor<'_> {
+        let inner_processor = Cairo1HintProcessor::new(&[], RunResources::default(), false);
+        Rpc1HintProcessor::new(inner_processor, configuration).unwrap()
+    }
+
+    #[test]
+    fn test_oracle_timeout() {
+        let configuration = Configuration::default();
+        let request_timeout = Duration::from_secs(240);
+
+        let mut processor = hint_processor(&configuration);
+        assert_eq!(
+            processor.oracle_timeout(request_timeout).unwrap(),
+            request_timeout
+        );
+
+        // The request timeout is capped to the time left before the deadline
+        let mut processor =
+            hint_processor(&configuration).with_timeout(Some(Duration::from_secs(60)));
+        let timeout = processor.oracle_timeout(request_timeout).unwrap();
+        assert!(timeout <= Duration::from_secs(60) && !timeout.is_zero());
+        assert!(!processor.timed_out());
+
+        let mut processor = hint_processor(&configuration).with_timeout(Some(Duration::ZERO));
+        assert!(processor.oracle_timeout(request_timeout).is_err());
+        assert!(processor.timed_out());
+    }
+}
@@ -0,0 +1,308 @@
+use std::{collections::BTreeMap, fmt, path::PathBuf, time::Duration};
+
+use cairo_vm::{
+    air_private_input::AirPrivateInput,
+    types::layout_name::LayoutName,
+    vm::{
+        runners::{cairo_pie::CairoPie, cairo_runner::ExecutionResources},
+        trace::trace_entry::RelocatedTraceEntry,
+    },
+    Felt252,
+};
+use serde::Serialize;
+
+use crate::{profiler::Profile, rpc_hint_processor::OracleCall};
+
+/// Options of a run.
+///
+/// Artifacts are kept in [`RunOutput::artifacts`] when requested,
+/// and are also written to a file when a path is given.
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub(crate) entry_func_name: String,
+    pub(crate) layout: LayoutName,
+    pub(crate) proof_mode: bool,
+    pub(crate) trace: bool,
+    pub(crate) trace_file: Option<PathBuf>,
+    pub(crate) memory: bool,
+    pub(crate) memory_file: Option<PathBuf>,
+    pub(crate) air_public_input: bool,
+    pub(crate) air_public_input_file: Option<PathBuf>,
+    pub(crate) prover_config: bool,
+    pub(crate) air_private_input: bool,
+    pub(crate) air_private_input_file: Option<PathBuf>,
+    pub(crate) cairo_pie: bool,
+    pub(crate) cairo_pie_file: Option<PathBuf>,
+    pub(crate) profile: bool,
+    pub(crate) initial_gas: Option<usize>,
+    pub(crate) max_steps: Option<usize>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) casm_cache_dir: Option<PathBuf>,
+    pub(crate) proof_wrapper: bool,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            entry_func_name: "::main".to_string(),
+            layout: LayoutName::all_cairo,
+            proof_mode: false,
+            trace: false,
+            trace_file: None,
+            memory: false,
+            memory_file: None,
+            air_public_input: false,
+            air_public_input_file: None,
+            prover_config: false,
+            air_private_input: false,
+            air_private_input_file: None,
+            cairo_pie: false,
+            cairo_pie_file: None,
+            profile: false,
+            initial_gas: None,
+            max_steps: None,
+            timeout: None,
+            casm_cache_dir: None,
+            proof_wrapper: false,
+        }
+    }
+}
+
+impl RunOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the first function whose name ends with `entry_func_name`, `::main` by default.
+    pub fn entry_function(mut self, entry_func_name: impl Into<String>) -> Self {
+        self.entry_func_name = entry_func_name.into();
+        self
+    }
+
+    pub fn layout(mut self, layout: LayoutName) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn proof_mode(mut self, proof_mode: bool) -> Self {
+        self.proof_mode = proof_mode;
+        self
+    }
+
+    /// Keeps the relocated trace.
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// Writes the encoded relocated trace to `trace_file`.
+    pub fn trace_file(mut self, trace_file: Option<PathBuf>) -> Self {
+        self.trace |= trace_file.is_some();
+        self.trace_file = trace_file;
+        self
+    }
+
+    /// Keeps the relocated memory.
+    pub fn memory(mut self, memory: bool) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    /// Writes the encoded relocated memory to `memory_file`.
+    pub fn memory_file(mut self, memory_file: Option<PathBuf>) -> Self {
+        self.memory |= memory_file.is_some();
+        self.memory_file = memory_file;
+        self
+    }
+
+    /// Keeps the AIR public input, serialized as JSON.
+    pub fn air_public_input(mut self, air_public_input: bool) -> Self {
+        self.air_public_input = air_public_input;
+        self
+    }
+
+    pub fn air_public_input_file(mut self, air_public_input_file: Option<PathBuf>) -> Self {
+        self.air_public_input |= air_public_input_file.is_some();
+        self.air_public_input_file = air_public_input_file;
+        self
+    }
+
+    /// Writes the Stone prover parameters of the run, see
+    /// [`write_prover_config`](crate::write_prover_config), next to `air_public_input_file`.
+    /// Existing [`CPU_AIR_PARAMS_FILE`](crate::prover_config::CPU_AIR_PARAMS_FILE) and
+    /// [`CPU_AIR_PROVER_CONFIG_FILE`](crate::prover_config::CPU_AIR_PROVER_CONFIG_FILE) files
+    /// in that directory are overwritten.
+    pub fn prover_config(mut self, prover_config: bool) -> Self {
+        self.prover_config = prover_config;
+        self
+    }
+
+    /// Keeps the AIR private input.
+    pub fn air_private_input(mut self, air_private_input: bool) -> Self {
+        self.air_private_input = air_private_input;
+        self
+    }
+
+    /// Writes the AIR private input to `air_private_input_file`.
+    /// The file refers to the trace and memory files, which must be set as well.
+    pub fn air_private_input_file(mut self, air_private_input_file: Option<PathBuf>) -> Self {
+        self.air_private_input |= air_private_input_file.is_some();
+        self.air_private_input_file = air_private_input_file;
+        self
+    }
+
+    /// Keeps the Cairo PIE.
+    pub fn cairo_pie(mut self, cairo_pie: bool) -> Self {
+        self.cairo_pie = cairo_pie;
+        self
+    }
+
+    /// Writes the Cairo PIE zip archive to `cairo_pie_file`.
+    pub fn cairo_pie_file(mut self, cairo_pie_file: Option<PathBuf>) -> Self {
+        self.cairo_pie |= cairo_pie_file.is_some();
+        self.cairo_pie_file = cairo_pie_file;
+        self
+    }
+
+    /// Attributes the steps and builtin usage of the run to the Cairo functions.
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Sets the gas available to a gas-enabled program,
+    /// [`DEFAULT_INITIAL_GAS`](crate::cairo_run::DEFAULT_INITIAL_GAS) by default.
+    pub fn initial_gas(mut self, initial_gas: Option<usize>) -> Self {
+        self.initial_gas = initial_gas;
+        self
+    }
+
+    /// Stops the run with [`Error::ResourceLimitExceeded`](crate::Error::ResourceLimitExceeded)
+    /// after `max_steps` VM steps.
+    pub fn max_steps(mut self, max_steps: Option<usize>) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Stops the run with [`Error::ResourceLimitExceeded`](crate::Error::ResourceLimitExceeded)
+    /// once it has run for `timeout`.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Reuses the CASM compiled by an earlier run of the same program from `casm_cache_dir`,
+    /// and stores it there otherwise.
+    pub fn casm_cache_dir(mut self, casm_cache_dir: Option<PathBuf>) -> Self {
+        self.casm_cache_dir = casm_cache_dir;
+        self
+    }
+
+    /// The entry function is a proof mode wrapper generated by `scarb agent-generate --proof-wrapper`.
+    /// Its argument holds the serialized arguments of the function it wraps, and its output is read
+    /// with the schema of that function.
+    pub fn proof_wrapper(mut self, proof_wrapper: bool) -> Self {
+        self.proof_wrapper = proof_wrapper;
+        self
+    }
+}
+
+/// A limit on the resources of a run, see [`RunOptions::max_steps`] and [`RunOptions::timeout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceLimit {
+    MaxSteps(usize),
+    Timeout(Duration),
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceLimit::MaxSteps(max_steps) => write!(f, "limit of {} steps", max_steps),
+            ResourceLimit::Timeout(timeout) => write!(f, "timeout of {:?}", timeout),
+        }
+    }
+}
+
+/// The result of a run.
+#[derive(Debug, Clone)]
+pub struct RunOutput {
+    /// The return value converted to JSON with the output schema, unless the run panicked
+    pub output: Option<String>,
+    /// The return value serialized as felts, unless the run panicked
+    pub return_values: Vec<Felt252>,
+    /// The panic data, if the run panicked
+    pub panic_data: Option<Vec<Felt252>>,
+    /// The gas consumed by the entry function, if the program uses gas
+    pub gas_consumed: Option<usize>,
+    pub resources: ExecutionResources,
+    /// The used size of each memory segment, by segment index
+    pub segment_sizes: Vec<usize>,
+    /// The oracle calls made by the run, in call order
+    pub oracle_calls: Vec<OracleCall>,
+    /// The profile of the run, when requested
+    pub profile: Option<Profile>,
+    pub artifacts: RunArtifacts,
+}
+
+impl RunOutput {
+    /// Summarizes the resources used by the run, including the oracle calls.
+    pub fn resources_report(&self) -> ResourcesReport {
+        let builtin_instance_counter = self
+            .resources
+            .builtin_instance_counter
+            .iter()
+            .map(|(builtin, count)| (builtin.to_str_with_suffix().to_string(), *count))
+            .collect();
+
+        let mut oracle_calls = BTreeMap::<String, OracleCallsReport>::new();
+        for call in &self.oracle_calls {
+            let report = oracle_calls.entry(call.selector.clone()).or_default();
+            report.count += 1;
+            report.wall_time_secs += call.duration.as_secs_f64();
+        }
+
+        ResourcesReport {
+            gas_consumed: self.gas_consumed,
+            n_steps: self.resources.n_steps,
+            n_memory_holes: self.resources.n_memory_holes,
+            builtin_instance_counter,
+            segment_sizes: self.segment_sizes.clone(),
+            oracle_wall_time_secs: oracle_calls
+                .values()
+                .map(|report| report.wall_time_secs)
+                .sum(),
+            oracle_calls,
+        }
+    }
+}
+
+/// The resources used by a run, to size prover jobs and pick a layout.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourcesReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_consumed: Option<usize>,
+    pub n_steps: usize,
+    pub n_memory_holes: usize,
+    pub builtin_instance_counter: BTreeMap<String, usize>,
+    pub segment_sizes: Vec<usize>,
+    /// The calls made to each oracle selector
+    pub oracle_calls: BTreeMap<String, OracleCallsReport>,
+    pub oracle_wall_time_secs: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OracleCallsReport {
+    pub count: usize,
+    pub wall_time_secs: f64,
+}
+
+/// The artifacts requested by the [`RunOptions`].
+#[derive(Debug, Clone, Default)]
+pub struct RunArtifacts {
+    pub trace: Option<Vec<RelocatedTraceEntry>>,
+    pub memory: Option<Vec<Option<Felt252>>>,
+    pub air_public_input: Option<String>,
+    pub air_private_input: Option<AirPrivateInput>,
+    pub cairo_pie: Option<CairoPie>,
+}
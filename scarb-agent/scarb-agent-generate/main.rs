@@ -1,9 +1,18 @@
+use anyhow::Result;
+use cairo_io_serde::{schema::CairoInput, schema_inference::infer_schema};
 use cairo_proto_build::Config;
 use clap::Parser;
-use scarb_agent_lib::utils::absolute_path;
-use scarb_metadata::MetadataCommand;
+use itertools::Itertools;
+use scarb_agent_lib::{
+    proof_wrapper::{generate_proof_wrappers, wrapped_functions, PROOF_WRAPPER_MODULE},
+    utils::{absolute_path, entry_function_name, has_function, load_sierra_program},
+};
+use scarb_metadata::{MetadataCommand, PackageMetadata, ScarbCommand};
 use scarb_ui::args::PackagesFilter;
-use std::{io::Result, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
 /// Execute the main function of a package.
 #[derive(Parser, Clone, Debug)]
@@ -24,6 +33,15 @@ struct Args {
 
     #[structopt(long)]
     oracle_lock: Option<PathBuf>,
+
+    /// Generate the wrappers running the functions in proof mode instead of the oracles
+    #[clap(long, default_value_t = false)]
+    proof_wrapper: bool,
+
+    /// The path of a function to wrap with `--proof-wrapper`, e.g. `strategies::run`.
+    /// `main` and the functions already wrapped in `proof_wrapper.cairo` are wrapped as well
+    #[clap(long = "function", requires = "proof_wrapper")]
+    functions: Vec<String>,
 }
 
 fn main() -> Result<()> {
@@ -31,14 +49,18 @@ fn main() -> Result<()> {
     let metadata = MetadataCommand::new().inherit_stderr().exec().unwrap();
     let package = args.packages_filter.match_one(&metadata).unwrap();
 
+    let cairo_output: PathBuf = absolute_path(&package, args.cairo_output.clone(), "cairo_output", Some(PathBuf::from("src")))
+        .expect("cairo output path must be provided either as an argument (--cairo-output src) or in the Scarb.toml file in the [tool.agent] section.");
+
+    if args.proof_wrapper {
+        return generate_proof_wrapper(&args, &package, &cairo_output);
+    }
+
     let definitions = absolute_path(&package, args.definitions, "definitions", None)
         .expect("oracle.proto definitions path must be provided either as an argument (--definitions proto/oracle.proto) or in the Scarb.toml file in [tool.agent] section.");
 
     let includes = definitions.parent().unwrap();
 
-    let cairo_output: PathBuf = absolute_path(&package, args.cairo_output, "cairo_output", Some(PathBuf::from("src")))
-        .expect("cairo output path must be provided either as an argument (--cairo-output src) or in the Scarb.toml file in the [tool.agent] section.");
-
     let oracle_module = args
         .oracle_module
         .or_else(|| {
@@ -60,3 +82,55 @@ fn main() -> Result<()> {
     println!("Done");
     Ok(())
 }
+
+// Writes the proof mode wrapper of each function to `proof_wrapper.cairo` and declares the module
+// in `lib.cairo`. The parameters of each function are read from its Sierra signature, so the
+// package is built first. Regenerating the module keeps its wrappers, and `main` is always
+// wrapped when the package has one.
+fn generate_proof_wrapper(
+    args: &Args,
+    package: &PackageMetadata,
+    cairo_output: &Path,
+) -> Result<()> {
+    ScarbCommand::new().arg("build").run()?;
+    let sierra_program = load_sierra_program(package)?;
+
+    let wrapper_path = cairo_output.join(format!("{}.cairo", PROOF_WRAPPER_MODULE));
+    let wrapped = fs::read_to_string(&wrapper_path)
+        .map(|code| wrapped_functions(&code))
+        .unwrap_or_default();
+    let function_paths = Some("main".to_string())
+        .filter(|main| has_function(&sierra_program, &entry_function_name(main)))
+        .into_iter()
+        .chain(wrapped)
+        .chain(args.functions.iter().cloned())
+        .map(|function_path| function_path.trim_start_matches("::").to_string())
+        .unique();
+
+    let functions = function_paths
+        .map(|function_path| {
+            // The wrapper passes the parameters of the Sierra signature, which a schema may
+            // group into a single struct
+            let signature = infer_schema(&sierra_program, &entry_function_name(&function_path))
+                .map_err(anyhow::Error::msg)?;
+            let params = match signature.cairo_input {
+                CairoInput::Params(params) => params.into_iter().map(|param| param.name).collect(),
+                CairoInput::Schema(_) => Vec::new(),
+            };
+            Ok((function_path, params))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    fs::create_dir_all(cairo_output)?;
+    fs::write(&wrapper_path, generate_proof_wrappers(&functions))?;
+
+    let lib_path = cairo_output.join("lib.cairo");
+    let lib = fs::read_to_string(&lib_path)?;
+    let declaration = format!("mod {};", PROOF_WRAPPER_MODULE);
+    if !lib.lines().any(|line| line.trim() == declaration) {
+        fs::write(&lib_path, format!("{}\n{}", declaration, lib))?;
+    }
+
+    println!("Generated {}", wrapper_path.display());
+    Ok(())
+}
@@ -1 +1,2 @@
+pub mod proof_wrapper;
 pub mod utils;
@@ -0,0 +1,177 @@
+use std::fmt::Write;
+
+use indoc::{formatdoc, indoc};
+
+/// The name of the module holding the proof mode wrappers, `src/proof_wrapper.cairo`.
+pub const PROOF_WRAPPER_MODULE: &str = "proof_wrapper";
+
+/// Returns the path of the proof mode wrapper of the function at `function_path`,
+/// e.g. `proof_wrapper::proof_main` for `main`.
+pub fn proof_wrapper_path(function_path: &str) -> String {
+    format!("{}::{}", PROOF_WRAPPER_MODULE, wrapper_name(function_path))
+}
+
+fn wrapper_name(function_path: &str) -> String {
+    format!(
+        "proof_{}",
+        function_path.trim_start_matches("::").replace("::", "_")
+    )
+}
+
+// The header line of the generated module listing the wrapped function paths, so that
+// regenerating the module keeps them
+const WRAPPED_FUNCTIONS_HEADER: &str = "// Wrapped functions: ";
+
+/// Returns the paths of the functions wrapped by a module generated by
+/// [`generate_proof_wrappers`], in the order of their wrappers.
+pub fn wrapped_functions(code: &str) -> Vec<String> {
+    code.lines()
+        .find_map(|line| line.strip_prefix(WRAPPED_FUNCTIONS_HEADER))
+        .map(|paths| {
+            paths
+                .split(", ")
+                .filter(|path| !path.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Generates the proof mode wrappers module, with a wrapper for each function path and the
+/// names of its parameters.
+///
+/// Proof mode only runs functions taking and returning an `Array<felt252>`, so each wrapper
+/// deserializes the arguments of its function from its input and serializes the return value.
+/// The types are inferred from the signature of the wrapped function, which only needs every
+/// parameter and the return type to implement `Serde`. The header of the module lists the
+/// wrapped functions, see [`wrapped_functions`].
+pub fn generate_proof_wrappers(functions: &[(String, Vec<String>)]) -> String {
+    let mut code = String::from(indoc! {"
+        // Generated by `scarb agent-generate --proof-wrapper`, do not edit.
+        // Each wrapper runs a function with its arguments deserialized from the input array,
+        // and returns its serialized return value, so that it can be run in proof mode.
+    "});
+    let function_paths = functions
+        .iter()
+        .map(|(function_path, _)| function_path.trim_start_matches("::"))
+        .collect::<Vec<_>>();
+    writeln!(
+        code,
+        "{WRAPPED_FUNCTIONS_HEADER}{}",
+        function_paths.join(", ")
+    )
+    .unwrap();
+
+    for (function_path, params) in functions {
+        let function_path = function_path.trim_start_matches("::");
+        let wrapper_name = wrapper_name(function_path);
+
+        let mut deserialize_args = String::new();
+        for (i, param) in params.iter().enumerate() {
+            writeln!(
+                deserialize_args,
+                "    // {param}\n    let arg_{i} = Serde::deserialize(ref input).expect('Invalid proof wrapper input');"
+            )
+            .unwrap();
+        }
+        let args = (0..params.len())
+            .map(|i| format!("arg_{i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        code.push_str(&formatdoc! {"
+
+            fn {wrapper_name}(input: Array<felt252>) -> Array<felt252> {{
+                let mut input = input.span();
+            {deserialize_args}    let result = crate::{function_path}({args});
+                let mut output = array![];
+                result.serialize(ref output);
+                output
+            }}
+        "});
+    }
+
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_wrapper_path() {
+        assert_eq!(proof_wrapper_path("main"), "proof_wrapper::proof_main");
+        assert_eq!(
+            proof_wrapper_path("::strategies::momentum::run"),
+            "proof_wrapper::proof_strategies_momentum_run"
+        );
+    }
+
+    #[test]
+    fn test_generate_proof_wrappers() {
+        let functions = [
+            ("main".to_string(), vec!["n".to_string()]),
+            (
+                "::strategies::momentum::run".to_string(),
+                vec!["prices".to_string(), "window".to_string()],
+            ),
+            ("strategies::reset".to_string(), vec![]),
+        ];
+
+        assert_eq!(
+            generate_proof_wrappers(&functions),
+            indoc! {"
+                // Generated by `scarb agent-generate --proof-wrapper`, do not edit.
+                // Each wrapper runs a function with its arguments deserialized from the input array,
+                // and returns its serialized return value, so that it can be run in proof mode.
+                // Wrapped functions: main, strategies::momentum::run, strategies::reset
+
+                fn proof_main(input: Array<felt252>) -> Array<felt252> {
+                    let mut input = input.span();
+                    // n
+                    let arg_0 = Serde::deserialize(ref input).expect('Invalid proof wrapper input');
+                    let result = crate::main(arg_0);
+                    let mut output = array![];
+                    result.serialize(ref output);
+                    output
+                }
+
+                fn proof_strategies_momentum_run(input: Array<felt252>) -> Array<felt252> {
+                    let mut input = input.span();
+                    // prices
+                    let arg_0 = Serde::deserialize(ref input).expect('Invalid proof wrapper input');
+                    // window
+                    let arg_1 = Serde::deserialize(ref input).expect('Invalid proof wrapper input');
+                    let result = crate::strategies::momentum::run(arg_0, arg_1);
+                    let mut output = array![];
+                    result.serialize(ref output);
+                    output
+                }
+
+                fn proof_strategies_reset(input: Array<felt252>) -> Array<felt252> {
+                    let mut input = input.span();
+                    let result = crate::strategies::reset();
+                    let mut output = array![];
+                    result.serialize(ref output);
+                    output
+                }
+            "}
+        );
+    }
+
+    #[test]
+    fn test_wrapped_functions() {
+        let functions = [
+            ("main".to_string(), vec!["n".to_string()]),
+            ("::strategies::run".to_string(), vec![]),
+        ];
+
+        assert_eq!(
+            wrapped_functions(&generate_proof_wrappers(&functions)),
+            vec!["main", "strategies::run"]
+        );
+        assert!(wrapped_functions(&generate_proof_wrappers(&[])).is_empty());
+        // Only the header lists the wrapped functions
+        assert!(wrapped_functions("let result = crate::main(arg_0);").is_empty());
+    }
+}
@@ -1,5 +1,20 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
 
+use anyhow::{Context, Result};
+use cairo_io_serde::{
+    schema::{parse_schema_file, Schema},
+    schema_inference::infer_schema,
+};
+use cairo_lang_sierra::program::{Program, VersionedProgram};
+use cairo_oracle_hint_processor::Error;
+use cairo_proto_serde::configuration::{Configuration, ServerConfig};
+use camino::Utf8PathBuf;
 use scarb_metadata::PackageMetadata;
 
 pub fn absolute_path(
@@ -31,3 +46,103 @@ pub fn absolute_path(
         Some(project_dir.join(definitions))
     }
 }
+
+/// Returns the target directory of the current Scarb profile, e.g. `target/dev`.
+pub fn scarb_profile_dir() -> Result<Utf8PathBuf> {
+    let scarb_target_dir = env::var("SCARB_TARGET_DIR").context("SCARB_TARGET_DIR not set")?;
+    let scarb_profile = env::var("SCARB_PROFILE").context("SCARB_PROFILE not set")?;
+    Ok(Utf8PathBuf::from(scarb_target_dir).join(scarb_profile))
+}
+
+/// Loads the Sierra program compiled by `scarb build` for the package.
+pub fn load_sierra_program(package: &PackageMetadata) -> Result<Program> {
+    let filename = format!("{}.sierra.json", package.name);
+    let path = scarb_profile_dir()?.join(filename);
+
+    if !path.try_exists()? {
+        anyhow::bail!(
+            "Package has not been compiled, file does not exist: {}",
+            path
+        );
+    }
+
+    Ok(
+        serde_json::from_str::<VersionedProgram>(&fs::read_to_string(&path)?)?
+            .into_v1()
+            .context("Failed to load Sierra program")?
+            .program,
+    )
+}
+
+/// Returns the name suffix of the Sierra function at `function_path`, relative to the package root.
+pub fn entry_function_name(function_path: &str) -> String {
+    format!("::{}", function_path.trim_start_matches("::"))
+}
+
+/// Returns whether `sierra_program` has a function whose name ends with `entry_func_name`.
+pub fn has_function(sierra_program: &Program, entry_func_name: &str) -> bool {
+    sierra_program.funcs.iter().any(|func| {
+        func.id
+            .debug_name
+            .as_ref()
+            .is_some_and(|name| name.ends_with(entry_func_name))
+    })
+}
+
+/// Loads the I/O schema of the package function at `function_path`, e.g. `main`.
+/// The schema file is an optional override, with a `functions` section for functions other than
+/// `main`. Otherwise the schema is derived from the Sierra signature.
+pub fn load_cairo_schema(
+    package: &PackageMetadata,
+    sierra_program: &Program,
+    function_path: &str,
+) -> Result<Schema> {
+    let schema_file = absolute_path(
+        package,
+        None,
+        "cairo_schema",
+        Some(PathBuf::from("cairo_schema.yaml")),
+    );
+    read_cairo_schema(schema_file.as_deref(), sierra_program, function_path)
+}
+
+/// Loads the I/O schema of the function at `function_path` from `schema_file` if it exists and
+/// describes the function, otherwise derives it from the Sierra signature.
+pub fn read_cairo_schema(
+    schema_file: Option<&Path>,
+    sierra_program: &Program,
+    function_path: &str,
+) -> Result<Schema> {
+    let entry_func_name = entry_function_name(function_path);
+    if let Some(schema_file) = schema_file {
+        if schema_file.try_exists()? {
+            let mut schema = parse_schema_file(&schema_file.to_path_buf())
+                .map_err(|e| anyhow::anyhow!("Failed to parse input schema: {}", e))?;
+            if schema.select_function(function_path) || entry_func_name == "::main" {
+                return Ok(schema);
+            }
+        }
+    }
+    infer_schema(sierra_program, &entry_func_name)
+        .map_err(|e| anyhow::anyhow!("Failed to infer input schema: {}", e))
+}
+
+/// Loads the oracle configuration from the `Oracle.lock` file and the servers config file.
+pub fn load_configuration(lock_output: &Path, servers_config_path: &Path) -> Result<Configuration> {
+    let lock_file = File::open(lock_output)
+        .with_context(|| format!("Failed to open {}", lock_output.display()))?;
+    let reader = BufReader::new(lock_file);
+    let mut service_configuration: Configuration = serde_json::from_reader(reader)?;
+
+    // Read and parse the servers config file
+    let config_content = fs::read_to_string(&servers_config_path).map_err(|e| Error::IO(e))?;
+    let servers_config: HashMap<String, ServerConfig> = serde_json::from_str(&config_content)
+        .map_err(|e| {
+            Error::ServersConfigFileError(format!("Failed to parse servers config: {}", e))
+        })?;
+
+    // Add the servers_config to the Configuration
+    service_configuration.servers_config = servers_config;
+
+    Ok(service_configuration)
+}
@@ -46,7 +46,7 @@ pub(crate) fn mk_cairo(
             # servers_config = "servers.json"
 
             [cairo]
-            enable-gas = false
+            enable-gas = true
         "#},
         )?;
     }
@@ -306,17 +306,15 @@ fn generate_cairo_schema_content() -> String {
     String::from(
         r#"
 schemas:
-    Input:
-        fields:
-            n: 
-                type: Primitive
-                name: i64
     Output:
         fields:
-            n:
+            - n:
                 type: Primitive
                 name: i64
-cairo_input: Input
+cairo_input:
+    - n:
+        type: Primitive
+        name: i64
 cairo_output: Output
     "#,
     )
@@ -0,0 +1,176 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use cairo_io_serde::{
+    cairo_input::{process_json_args, serialize_json_args},
+    FuncArg, FuncArgs,
+};
+use cairo_oracle_hint_processor::{run_1, write_prove_bundle, RunOptions};
+use cairo_vm::types::layout_name::LayoutName;
+use clap::Parser;
+use scarb_agent_lib::{
+    proof_wrapper::proof_wrapper_path,
+    utils::{
+        absolute_path, entry_function_name, has_function, load_cairo_schema, load_configuration,
+        load_sierra_program, scarb_profile_dir,
+    },
+};
+use scarb_metadata::{MetadataCommand, ScarbCommand};
+use scarb_ui::args::PackagesFilter;
+use serde_json::Value;
+
+/// Runs a function in proof mode and archives everything needed to prove the run in a zip
+/// bundle, with a manifest of the hashes of its files.
+#[derive(Parser, Clone, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    packages_filter: PackagesFilter,
+
+    #[clap(long, default_value_t = false)]
+    no_build: bool,
+
+    #[clap(long = "layout", default_value = "all_cairo", value_parser = validate_layout)]
+    layout: String,
+
+    /// The path of the function to run, relative to the package root, e.g. `strategies::run`
+    #[clap(long, default_value = "main")]
+    function: String,
+
+    #[clap(long)]
+    oracle_lock: Option<PathBuf>,
+
+    /// The path of the bundle, `<package>.bundle.zip` in the Scarb profile target directory by
+    /// default
+    #[clap(long, short)]
+    output: Option<PathBuf>,
+
+    #[clap(long = "args", default_value = "")]
+    args: Option<String>,
+
+    /// Read the arguments from a file
+    #[clap(long, conflicts_with = "args")]
+    args_file: Option<PathBuf>,
+}
+
+fn validate_layout(value: &str) -> Result<String, String> {
+    match value {
+        "plain"
+        | "small"
+        | "dex"
+        | "starknet"
+        | "starknet_with_keccak"
+        | "recursive_large_output"
+        | "all_cairo"
+        | "all_solidity"
+        | "dynamic" => Ok(value.to_string()),
+        _ => Err(format!("{value} is not a valid layout")),
+    }
+}
+
+fn str_into_layout(value: &str) -> LayoutName {
+    match value {
+        "plain" => LayoutName::plain,
+        "small" => LayoutName::small,
+        "dex" => LayoutName::dex,
+        "recursive" => LayoutName::recursive,
+        "starknet" => LayoutName::starknet,
+        "starknet_with_keccak" => LayoutName::starknet_with_keccak,
+        "recursive_large_output" => LayoutName::recursive_large_output,
+        "recursive_with_poseidon" => LayoutName::recursive_with_poseidon,
+        "all_solidity" => LayoutName::all_solidity,
+        "all_cairo" => LayoutName::all_cairo,
+        "dynamic" => LayoutName::dynamic,
+        _ => LayoutName::all_cairo,
+    }
+}
+
+fn main() -> Result<()> {
+    let args: Args = Args::parse();
+
+    let metadata = MetadataCommand::new().inherit_stderr().exec()?;
+    let package = args.packages_filter.match_one(&metadata)?;
+
+    if !args.no_build {
+        ScarbCommand::new().arg("build").run()?;
+    }
+    let sierra_program = load_sierra_program(&package)?;
+    let schema = load_cairo_schema(&package, &sierra_program, &args.function)?;
+
+    let lock_output = absolute_path(&package, args.clone().oracle_lock, "oracle_lock", Some(PathBuf::from("Oracle.lock")))
+        .context("Lock path must be provided either as an argument (--oracle-lock src) or in the Scarb.toml file in the [tool.agent] section.")?;
+    let servers_config_path = absolute_path(&package, None, "servers_config", Some(PathBuf::from("servers.json")))
+        .context("servers config path must be provided either in the Scarb.toml file in the [tool.agent] section or default to servers.json in the project root.")?;
+    let configuration = load_configuration(&lock_output, &servers_config_path)?;
+
+    let json_args = match &args.args_file {
+        Some(args_file) => fs::read_to_string(args_file)
+            .with_context(|| format!("Failed to read {}", args_file.display()))?,
+        None => args.args.clone().unwrap_or_default(),
+    };
+    // The bundle keeps the input as JSON, a function without arguments taking an empty object
+    let input: Value = if json_args.trim().is_empty() {
+        Value::Object(Default::default())
+    } else {
+        serde_json::from_str(&json_args).context("The arguments are not valid JSON")?
+    };
+
+    // The function runs through its generated wrapper if there is one, which takes its
+    // arguments serialized in a single `Array<felt252>`
+    let proof_wrapper = Some(entry_function_name(&proof_wrapper_path(&args.function)))
+        .filter(|wrapper| has_function(&sierra_program, wrapper));
+    let func_args = if proof_wrapper.is_some() {
+        let json_args = Some(json_args.as_str()).filter(|json_args| !json_args.trim().is_empty());
+        let serialized_args = serialize_json_args(json_args.unwrap_or("{}"), &schema)?;
+        FuncArgs(vec![FuncArg::Array(serialized_args)])
+    } else if json_args.trim().is_empty() {
+        FuncArgs::default()
+    } else {
+        process_json_args(&json_args, &schema)?
+    };
+    let entry_function = proof_wrapper
+        .clone()
+        .unwrap_or_else(|| entry_function_name(&args.function));
+
+    let options = RunOptions::new()
+        .entry_function(entry_function.clone())
+        .proof_wrapper(proof_wrapper.is_some())
+        .layout(str_into_layout(&args.layout))
+        .proof_mode(true)
+        .trace(true)
+        .memory(true)
+        .air_public_input(true)
+        .air_private_input(true)
+        .casm_cache_dir(Some(
+            scarb_profile_dir()?.join("casm-cache").into_std_path_buf(),
+        ));
+
+    let run_output = run_1(
+        &configuration,
+        &sierra_program,
+        &schema,
+        &func_args,
+        &options,
+    )?;
+
+    let output = match args.output {
+        Some(output) => output,
+        None => scarb_profile_dir()?
+            .join(format!("{}.bundle.zip", package.name))
+            .into_std_path_buf(),
+    };
+    let manifest = write_prove_bundle(
+        &output,
+        &sierra_program,
+        &entry_function,
+        &args.layout,
+        &input,
+        &run_output,
+    )
+    .with_context(|| format!("Failed to write the prove bundle {}", output.display()))?;
+
+    eprintln!("Wrote the prove bundle {}", output.display());
+    println!("{}", serde_json::to_string_pretty(&manifest)?);
+
+    Ok(())
+}
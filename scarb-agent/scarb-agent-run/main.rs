@@ -1,24 +1,32 @@
 use std::{
-    collections::HashMap,
     env,
     fs::{self, File},
-    io::BufReader,
-    path::PathBuf,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
 use anyhow::{Context, Result};
 use cairo_io_serde::{
-    cairo_input::process_json_args,
-    schema::{parse_schema_file, Schema},
-    FuncArgs,
+    cairo_input::{process_json_args, serialize_json_args},
+    schema::Schema,
+    FuncArg, FuncArgs, IoSerdeError,
 };
-use cairo_lang_sierra::program::VersionedProgram;
-use cairo_oracle_hint_processor::{run_1, Error};
-use cairo_proto_serde::configuration::{Configuration, ServerConfig};
+use cairo_lang_sierra::program::Program;
+use cairo_oracle_hint_processor::{
+    compile_cairo_project, run_1, AgentRunner, Error, Profile, RunOptions, RunOutput,
+};
+use cairo_proto_serde::configuration::Configuration;
 use cairo_vm::types::layout_name::LayoutName;
-use camino::Utf8PathBuf;
 use clap::Parser;
-use scarb_agent_lib::utils::absolute_path;
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use scarb_agent_lib::{
+    proof_wrapper::proof_wrapper_path,
+    utils::{
+        absolute_path, entry_function_name, has_function, load_cairo_schema, load_configuration,
+        load_sierra_program, read_cairo_schema, scarb_profile_dir,
+    },
+};
 use scarb_metadata::{MetadataCommand, ScarbCommand};
 use scarb_ui::args::PackagesFilter;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -33,9 +41,22 @@ struct Args {
     #[clap(long, default_value_t = false)]
     no_build: bool,
 
+    /// Compile the Cairo project in this directory in-process instead of the Scarb package.
+    /// The directory holds a `cairo_project.toml` file, `Oracle.lock` and `servers.json`
+    #[clap(long, conflicts_with = "no_build")]
+    source: Option<PathBuf>,
+
+    /// Meter gas when compiling with `--source`, like Scarb's `enable-gas`
+    #[clap(long, default_value_t = false, requires = "source")]
+    enable_gas: bool,
+
     #[clap(long = "layout", default_value = "all_cairo", value_parser = validate_layout)]
     layout: String,
 
+    /// The path of the function to run, relative to the package root, e.g. `strategies::run`
+    #[clap(long, default_value = "main")]
+    function: String,
+
     #[clap(long, default_value_t = false)]
     proof_mode: bool,
 
@@ -45,9 +66,16 @@ struct Args {
     )]
     cairo_pie_output: Option<PathBuf>,
 
+    /// Write the AIR public input to a file. The `cpu_air_params.json` and
+    /// `cpu_air_prover_config.json` of the Stone prover are written to the same directory,
+    /// overwriting existing ones, unless `--no-prover-config` is set
     #[clap(long = "air-public-input", requires = "proof-mode")]
     air_public_input: Option<PathBuf>,
 
+    /// Don't write the Stone prover parameters next to `--air-public-input`
+    #[clap(long, default_value_t = false, requires = "air_public_input")]
+    no_prover_config: bool,
+
     #[clap(
         long = "air_private_input",
         requires_all = ["proof-mode", "trace-file", "memory-file"] 
@@ -66,9 +94,51 @@ struct Args {
     #[clap(long)]
     memory_file: Option<PathBuf>,
 
+    /// Write the steps, builtins, memory and oracle calls used by the run to a JSON file
+    #[clap(long)]
+    resources_report: Option<PathBuf>,
+
+    /// Write the steps spent in each function stack to a folded stacks file, for flamegraph tools.
+    /// The builtin usage is written next to it, in `<profile>.<builtin>.folded` files
+    #[clap(long)]
+    profile: Option<PathBuf>,
+
+    /// Gas available to the run of a gas-enabled program
+    #[clap(long)]
+    initial_gas: Option<usize>,
+
+    /// Stop the run after this many VM steps
+    #[clap(long)]
+    max_steps: Option<usize>,
+
+    /// Stop the run after this many seconds
+    #[clap(long, value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Directory of the CASM cache, `casm-cache` in the Scarb profile target directory by default
+    #[clap(long)]
+    casm_cache_dir: Option<PathBuf>,
+
+    /// Always compile the program to CASM, without reading or writing the CASM cache
+    #[clap(long, default_value_t = false, conflicts_with = "casm_cache_dir")]
+    no_casm_cache: bool,
+
     #[clap(long = "args", default_value = "")]
     args: Option<String>,
 
+    /// Read the arguments from a file, or one JSON value per line with `--batch`
+    #[clap(long, conflicts_with = "args")]
+    args_file: Option<PathBuf>,
+
+    /// Run every line of `--args-file` and print one JSON result per line
+    #[clap(
+        long,
+        default_value_t = false,
+        requires = "args_file",
+        conflicts_with_all = ["proof_mode", "trace_file", "memory_file", "cairo_pie_output", "air_public_input", "air_private_input", "resources_report", "profile"]
+    )]
+    batch: bool,
+
     #[clap(long, default_value_t = false)]
     preprocess: bool,
 
@@ -91,6 +161,14 @@ fn validate_layout(value: &str) -> Result<String, String> {
     }
 }
 
+fn parse_timeout(value: &str) -> Result<Duration, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| format!("{value} is not a valid number of seconds"))
+}
+
 fn str_into_layout(value: &str) -> LayoutName {
     match value {
         "plain" => LayoutName::plain,
@@ -119,153 +197,336 @@ struct CairoRunResponse {
     request_id: String,
 }
 
+/// The output of a run, or its panic message, and the gas it consumed.
+struct AgentOutput {
+    output: String,
+    gas_consumed: Option<usize>,
+}
+
 fn main() -> Result<()> {
-    let result = match run() {
-        Ok(return_values) => {
-            let parsed_data: Value = serde_json::from_str(&return_values)?;
-            json!({
-                "status": "success",
-                "data": parsed_data
-            })
+    let args: Args = Args::parse();
+
+    let success = if args.batch {
+        run_batch(&args)?
+    } else {
+        let result = response(run(&args))?;
+        println!("{}", serde_json::to_string(&result)?);
+        result["status"] == "success"
+    };
+
+    std::process::exit(if success { 0 } else { 1 });
+}
+
+fn response(result: Result<AgentOutput>) -> Result<Value> {
+    Ok(match result {
+        Ok(AgentOutput {
+            output,
+            gas_consumed,
+        }) => {
+            let mut response = match serde_json::from_str::<Value>(&output) {
+                Ok(parsed_data) => json!({
+                    "status": "success",
+                    "data": parsed_data
+                }),
+                // e.g. the panic message of the run
+                Err(_) => json!({
+                    "status": "error",
+                    "message": output
+                }),
+            };
+            if let Some(gas_consumed) = gas_consumed {
+                response["gas_consumed"] = json!(gas_consumed);
+            }
+            response
         }
         Err(err) => {
-            json!({
+            let mut response = json!({
                 "status": "error",
                 "message": err.to_string()
-            })
+            });
+            // Input and output conversion errors carry their kind and JSON path
+            if let Some(io_serde_error) = io_serde_error(&err) {
+                response["error"] = serde_json::to_value(io_serde_error)?;
+            }
+            // Runs stopped by a limit carry the resources used until then
+            if let Some(Error::ResourceLimitExceeded { limit, resources }) =
+                err.downcast_ref::<Error>()
+            {
+                response["limit"] = serde_json::to_value(limit)?;
+                response["resources"] = serde_json::to_value(resources)?;
+            }
+            response
         }
+    })
+}
+
+fn io_serde_error(err: &anyhow::Error) -> Option<&IoSerdeError> {
+    match err.downcast_ref::<Error>() {
+        Some(Error::IoSerde(io_serde_error)) => Some(io_serde_error),
+        _ => err.downcast_ref::<IoSerdeError>(),
+    }
+}
+
+/// The package program and configuration, loaded once and shared by every run.
+struct Agent {
+    configuration: Configuration,
+    schema: Schema,
+    sierra_program: Program,
+    /// Where the compiled CASM is cached, if anywhere
+    casm_cache_dir: Option<PathBuf>,
+}
+
+fn run(args: &Args) -> Result<AgentOutput> {
+    let agent = load_agent(args)?;
+    let json_args = match &args.args_file {
+        Some(args_file) => Some(
+            fs::read_to_string(args_file)
+                .with_context(|| format!("Failed to read {}", args_file.display()))?,
+        ),
+        None => args.args.clone(),
     };
+    run_agent(&agent, args, json_args.as_deref())
+}
 
-    println!("{}", serde_json::to_string(&result)?);
+// Runs every line of the args file in parallel and prints the results in input order.
+// The program is compiled once and shared by all the runs. Returns whether all runs succeeded.
+fn run_batch(args: &Args) -> Result<bool> {
+    let runner = load_agent(args).and_then(|agent| {
+        let options = RunOptions::new()
+            .entry_function(entry_function_name(&args.function))
+            .layout(str_into_layout(&args.layout))
+            .initial_gas(args.initial_gas)
+            .max_steps(args.max_steps)
+            .timeout(args.timeout)
+            .casm_cache_dir(agent.casm_cache_dir);
+        AgentRunner::new(
+            &agent.sierra_program,
+            agent.configuration,
+            agent.schema,
+            &options,
+        )
+        .map_err(anyhow::Error::from)
+    });
+    let runner = match runner {
+        Ok(runner) => runner,
+        Err(err) => {
+            println!("{}", serde_json::to_string(&response(Err(err))?)?);
+            return Ok(false);
+        }
+    };
 
-    std::process::exit(if result["status"] == "error" { 1 } else { 0 });
+    let args_file = args.args_file.as_ref().context("Expect --args-file")?;
+    let reader = BufReader::new(
+        File::open(args_file).with_context(|| format!("Failed to open {}", args_file.display()))?,
+    );
+    let inputs = reader
+        .lines()
+        .enumerate()
+        .map(|(line, input)| input.map(|input| (line + 1, input)))
+        .filter(|input| !matches!(input, Ok((_, input)) if input.trim().is_empty()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let results = inputs
+        .into_par_iter()
+        .map(|(line, input)| -> Result<Value> {
+            let result = get_func_args(args, Some(&input), runner.schema(), false)
+                .and_then(|func_args| process_result(runner.run(&func_args), args.postprocess));
+            let mut result = response(result)?;
+            result["line"] = json!(line);
+            Ok(result)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for result in &results {
+        println!("{}", serde_json::to_string(result)?);
+    }
+
+    Ok(results.iter().all(|result| result["status"] == "success"))
 }
 
-fn run() -> Result<String> {
-    let args: Args = Args::parse();
+fn load_agent(args: &Args) -> Result<Agent> {
+    if let Some(source) = &args.source {
+        return load_source_agent(args, source);
+    }
+
     let metadata = MetadataCommand::new().inherit_stderr().exec()?;
     let package = args.packages_filter.match_one(&metadata)?;
 
     if !args.no_build {
         ScarbCommand::new().arg("build").run()?;
     }
-    let filename = format!("{}.sierra.json", package.name);
-    let scarb_target_dir = env::var("SCARB_TARGET_DIR").context("SCARB_TARGET_DIR not set")?;
-    let scarb_profile = env::var("SCARB_PROFILE").context("SCARB_PROFILE not set")?;
-    let path = Utf8PathBuf::from(scarb_target_dir)
-        .join(scarb_profile)
-        .join(filename);
-
-    if !path.try_exists()? {
-        anyhow::bail!(
-            "Package has not been compiled, file does not exist: {}",
-            path
-        );
-    }
+    let sierra_program = load_sierra_program(&package)?;
 
     let lock_output = absolute_path(&package, args.clone().oracle_lock, "oracle_lock", Some(PathBuf::from("Oracle.lock")))
         .context("Lock path must be provided either as an argument (--oracle-lock src) or in the Scarb.toml file in the [tool.agent] section.")?;
-    let lock_file = File::open(lock_output)?;
-    let reader = BufReader::new(lock_file);
-    let mut service_configuration: Configuration = serde_json::from_reader(reader)?;
 
     // Get the servers config path using absolute_path
     let servers_config_path = absolute_path(&package, None, "servers_config", Some(PathBuf::from("servers.json")))
         .expect("servers config path must be provided either in the Scarb.toml file in the [tool.agent] section or default to servers.json in the project root.");
 
-    // Read and parse the servers config file
-    let config_content = fs::read_to_string(&servers_config_path).map_err(|e| Error::IO(e))?;
-    let servers_config: HashMap<String, ServerConfig> = serde_json::from_str(&config_content)
-        .map_err(|e| {
-            Error::ServersConfigFileError(format!("Failed to parse servers config: {}", e))
-        })?;
+    let schema = load_cairo_schema(&package, &sierra_program, &args.function)?;
+
+    let casm_cache_dir = if args.no_casm_cache {
+        None
+    } else {
+        match &args.casm_cache_dir {
+            Some(casm_cache_dir) => Some(casm_cache_dir.clone()),
+            None => Some(scarb_profile_dir()?.join("casm-cache").into_std_path_buf()),
+        }
+    };
+
+    Ok(Agent {
+        configuration: load_configuration(&lock_output, &servers_config_path)?,
+        schema,
+        sierra_program,
+        casm_cache_dir,
+    })
+}
 
-    // Add the servers_config to the Configuration
-    service_configuration.servers_config = servers_config;
+// Compiles a Cairo project without Scarb, its files are read from the project directory
+fn load_source_agent(args: &Args, source: &Path) -> Result<Agent> {
+    let sierra_program = compile_cairo_project(source, args.enable_gas)?;
 
-    let sierra_program = serde_json::from_str::<VersionedProgram>(&fs::read_to_string(&path)?)?
-        .into_v1()
-        .context("Failed to load Sierra program")?
-        .program;
+    let lock_output = args
+        .oracle_lock
+        .clone()
+        .unwrap_or_else(|| source.join("Oracle.lock"));
+    let configuration = load_configuration(&lock_output, &source.join("servers.json"))?;
 
-    let schema_file = get_cairo_schema(&package)?;
-    let schema = parse_schema_file(&schema_file)
-        .map_err(|e| anyhow::anyhow!("Failed to parse input schema: {}", e))?;
+    let schema = read_cairo_schema(
+        Some(&source.join("cairo_schema.yaml")),
+        &sierra_program,
+        &args.function,
+    )?;
+
+    // There is no target directory to keep the cache in by default
+    let casm_cache_dir = args.casm_cache_dir.clone().filter(|_| !args.no_casm_cache);
+
+    Ok(Agent {
+        configuration,
+        schema,
+        sierra_program,
+        casm_cache_dir,
+    })
+}
 
-    let func_args = get_func_args(&args, &schema)?;
+fn run_agent(agent: &Agent, args: &Args, json_args: Option<&str>) -> Result<AgentOutput> {
+    // In proof mode, the function runs through its generated wrapper if there is one
+    let proof_wrapper = Some(entry_function_name(&proof_wrapper_path(&args.function)))
+        .filter(|wrapper| args.proof_mode && has_function(&agent.sierra_program, wrapper));
+    let func_args = get_func_args(args, json_args, &agent.schema, proof_wrapper.is_some())?;
+
+    let options = RunOptions::new()
+        .entry_function(
+            proof_wrapper
+                .clone()
+                .unwrap_or_else(|| entry_function_name(&args.function)),
+        )
+        .proof_wrapper(proof_wrapper.is_some())
+        .layout(str_into_layout(&args.layout))
+        .proof_mode(args.proof_mode)
+        .trace_file(args.trace_file.clone())
+        .memory_file(args.memory_file.clone())
+        .cairo_pie_file(args.cairo_pie_output.clone())
+        .air_public_input_file(args.air_public_input.clone())
+        .prover_config(!args.no_prover_config)
+        .air_private_input_file(args.air_private_input.clone())
+        .profile(args.profile.is_some())
+        .initial_gas(args.initial_gas)
+        .max_steps(args.max_steps)
+        .timeout(args.timeout)
+        .casm_cache_dir(agent.casm_cache_dir.clone());
 
     let result = run_1(
-        &service_configuration,
-        &str_into_layout(&args.layout),
-        &args.trace_file,
-        &args.memory_file,
-        &args.cairo_pie_output,
-        &args.air_public_input,
-        &args.air_private_input,
+        &agent.configuration,
+        &agent.sierra_program,
+        &agent.schema,
         &func_args,
-        &schema,
-        &sierra_program,
-        "::main",
-        args.proof_mode,
+        &options,
     );
 
+    if let (Ok(run_output), Some(resources_report)) = (&result, &args.resources_report) {
+        let report = serde_json::to_string_pretty(&run_output.resources_report())?;
+        fs::write(resources_report, report)
+            .with_context(|| format!("Failed to write {}", resources_report.display()))?;
+    }
+    if let (Ok(run_output), Some(profile_path)) = (&result, &args.profile) {
+        if let Some(profile) = &run_output.profile {
+            write_profile(profile, profile_path)?;
+        }
+    }
+
     process_result(result, args.postprocess)
 }
 
-fn get_func_args(args: &Args, schema: &Schema) -> Result<FuncArgs> {
+fn write_profile(profile: &Profile, path: &Path) -> Result<()> {
+    fs::write(path, profile.folded_steps())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    for (builtin, weights) in &profile.builtins {
+        if weights.is_empty() {
+            continue;
+        }
+        let builtin_path = path.with_extension(format!("{}.folded", builtin));
+        let folded = profile.folded_builtin(builtin).unwrap_or_default();
+        fs::write(&builtin_path, folded)
+            .with_context(|| format!("Failed to write {}", builtin_path.display()))?;
+    }
+    Ok(())
+}
+
+// `serialized` passes the arguments serialized in a single `Array<felt252>`, as proof mode wrappers
+// expect them
+fn get_func_args(
+    args: &Args,
+    json_args: Option<&str>,
+    schema: &Schema,
+    serialized: bool,
+) -> Result<FuncArgs> {
     if args.preprocess {
-        preprocess_args(args, schema)
+        preprocess_args(json_args, schema, serialized)
     } else {
-        process_args(args, schema)
+        process_args(json_args, schema, serialized)
     }
 }
 
-fn preprocess_args(args: &Args, schema: &Schema) -> Result<FuncArgs> {
+fn json_to_func_args(json_args: &str, schema: &Schema, serialized: bool) -> Result<FuncArgs> {
+    if serialized {
+        let serialized_args = serialize_json_args(json_args, schema)?;
+        Ok(FuncArgs(vec![FuncArg::Array(serialized_args)]))
+    } else {
+        process_json_args(json_args, schema).map_err(anyhow::Error::from)
+    }
+}
+
+fn preprocess_args(json_args: Option<&str>, schema: &Schema, serialized: bool) -> Result<FuncArgs> {
     let preprocess_url = env::var("PREPROCESS_URL")
         .unwrap_or_else(|_| "http://localhost:3000/preprocess".to_string());
 
-    let body: Value = serde_json::from_str(&args.args.as_ref().context("Expect --args")?)?;
+    let body: Value = serde_json::from_str(json_args.context("Expect --args")?)?;
 
     let preprocess_result = call_server::<PreprocessResponse>(&preprocess_url, Some(body))?.args;
-    process_json_args(&preprocess_result, schema).map_err(|e| anyhow::anyhow!(e))
+    json_to_func_args(&preprocess_result, schema, serialized)
 }
 
-fn process_args(args: &Args, schema: &Schema) -> Result<FuncArgs> {
-    match &args.args {
+fn process_args(json_args: Option<&str>, schema: &Schema, serialized: bool) -> Result<FuncArgs> {
+    match json_args {
         Some(json_args) if !json_args.trim().is_empty() => {
-            process_json_args(json_args, schema).map_err(|e| anyhow::anyhow!(e))
+            json_to_func_args(json_args, schema, serialized)
         }
+        // A wrapper still takes its (empty) input array
+        _ if serialized => json_to_func_args("{}", schema, serialized),
         _ => Ok(FuncArgs::default()),
     }
 }
 
-fn get_cairo_schema(package: &scarb_metadata::PackageMetadata) -> Result<PathBuf> {
-    absolute_path(package, None, "cairo_schema", Some(PathBuf::from("cairo_schema.yaml")))
-        .context("Cairo schema path must be provided either in the Scarb.toml file in the [tool.agent] section or default to cairo_schema.yaml in the project root.")
-}
-
-fn process_result(result: Result<Option<String>, Error>, postprocess: bool) -> Result<String> {
-    match result {
-        Ok(return_values) => {
-            let cairo_output = return_values.unwrap_or_else(|| "Null".to_string());
-
-            if postprocess {
-                let postprocess_url = env::var("POSTPROCESS_URL")
-                    .unwrap_or_else(|_| "http://localhost:3000/postprocess".to_string());
-
-                let body = CairoRunResponse {
-                    result: cairo_output,
-                    request_id: "None".to_string(),
-                };
-
-                call_server::<Value>(&postprocess_url, Some(body))
-                    .map(|v| v.to_string())
-                    .map_err(|e| e.into())
-            } else {
-                Ok(cairo_output)
-            }
-        }
-        Err(Error::RunPanic(panic_data)) => {
+fn process_result(result: Result<RunOutput, Error>, postprocess: bool) -> Result<AgentOutput> {
+    let run_output = result?;
+    let gas_consumed = run_output.gas_consumed;
+    let output = match run_output {
+        RunOutput {
+            panic_data: Some(panic_data),
+            ..
+        } => {
             let panic_data_string = if panic_data.is_empty() {
                 "Null".to_string()
             } else {
@@ -279,10 +540,31 @@ fn process_result(result: Result<Option<String>, Error>, postprocess: bool) -> R
                     .collect::<Vec<_>>()
                     .join(", ")
             };
-            Ok(format!("Run panicked with: [{}]", panic_data_string))
+            format!("Run panicked with: [{}]", panic_data_string)
         }
-        Err(err) => Err(err.into()),
-    }
+        run_output => {
+            let cairo_output = run_output.output.unwrap_or_else(|| "Null".to_string());
+
+            if postprocess {
+                let postprocess_url = env::var("POSTPROCESS_URL")
+                    .unwrap_or_else(|_| "http://localhost:3000/postprocess".to_string());
+
+                let body = CairoRunResponse {
+                    result: cairo_output,
+                    request_id: "None".to_string(),
+                };
+
+                call_server::<Value>(&postprocess_url, Some(body))?.to_string()
+            } else {
+                cairo_output
+            }
+        }
+    };
+
+    Ok(AgentOutput {
+        output,
+        gas_consumed,
+    })
 }
 
 fn call_server<T: DeserializeOwned>(
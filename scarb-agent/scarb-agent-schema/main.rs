@@ -0,0 +1,64 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use cairo_io_serde::json_schema::{input_json_schema, output_json_schema};
+use clap::Parser;
+use scarb_agent_lib::utils::{load_cairo_schema, load_sierra_program};
+use scarb_metadata::{MetadataCommand, ScarbCommand};
+use scarb_ui::args::PackagesFilter;
+use serde_json::json;
+
+/// Print the JSON Schema of the `--args` payload and of the output of `scarb agent-run`.
+#[derive(Parser, Clone, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    packages_filter: PackagesFilter,
+
+    #[clap(long, default_value_t = false)]
+    no_build: bool,
+
+    /// The path of the function, relative to the package root, e.g. `strategies::run`
+    #[clap(long, default_value = "main")]
+    function: String,
+
+    /// Write `input.schema.json` and `output.schema.json` to this directory instead of printing them
+    #[clap(long)]
+    output_dir: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args: Args = Args::parse();
+    let metadata = MetadataCommand::new().inherit_stderr().exec()?;
+    let package = args.packages_filter.match_one(&metadata)?;
+
+    if !args.no_build {
+        ScarbCommand::new().arg("build").run()?;
+    }
+
+    let sierra_program = load_sierra_program(&package)?;
+    let schema = load_cairo_schema(&package, &sierra_program, &args.function)?;
+
+    let input = input_json_schema(&schema)?;
+    let output = output_json_schema(&schema)?;
+
+    match args.output_dir {
+        Some(output_dir) => {
+            fs::create_dir_all(&output_dir)
+                .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+            for (filename, document) in
+                [("input.schema.json", input), ("output.schema.json", output)]
+            {
+                let path = output_dir.join(filename);
+                fs::write(&path, serde_json::to_string_pretty(&document)?)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+            }
+        }
+        None => println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "input": input, "output": output }))?
+        ),
+    }
+
+    Ok(())
+}